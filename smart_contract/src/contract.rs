@@ -4,10 +4,12 @@ use crate::execute::{self, init, update_menu_item};
 use crate::migrate;
 use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::query::{
-    get_all_restaurants, get_escrow, get_latest_order_id, get_menu_items_for_restaurant,
-    get_order_by_id, get_order_cost, get_order_status, get_order_status_by_id,
-    get_orders_for_restaurant, get_owners, get_rider, get_rider_by_address, get_user,
-    get_user_orders, get_user_restaurants, query_platform_config,
+    get_all_restaurants, get_claimable_balance, get_escrow, get_latest_order_id, get_menu,
+    get_menu_items_for_restaurant, get_open_delivery_jobs, get_order_by_id, get_order_cost,
+    get_order_status, get_order_status_by_id, get_orders_for_restaurant, get_owners,
+    get_restaurant_operators, get_restaurant_reviews, get_rider, get_rider_by_address,
+    get_rider_orders, get_rider_stats, get_user, get_user_orders, get_user_restaurants,
+    query_platform_config,
 };
 
 use cosmwasm_std::entry_point;
@@ -35,6 +37,12 @@ pub fn instantiate(
         msg.owner_address,
         msg.fee_percentage,
         msg.fee_address,
+        msg.order_acceptance_timeout,
+        msg.delivery_fees,
+        msg.rider_suspension_threshold,
+        msg.dispute_window,
+        msg.accepted_denoms,
+        msg.accepted_cw20s,
     )
     .map_err(|e| StdError::generic_err(e.to_string()))?)
 }
@@ -44,6 +52,15 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
     match msg {
         MigrateMsg::ConvertToMultiOwner {} => migrate::migrate(deps, env, msg),
         MigrateMsg::UpdateRidersAndUsers {} => migrate::update_rider_and_add_user(deps, env),
+        MigrateMsg::BuildOrderIndexes { start_after, limit } => {
+            migrate::build_order_indexes(deps, env, start_after, limit)
+        }
+        MigrateMsg::SplitRestaurantAddresses { start_after, limit } => {
+            migrate::split_restaurant_addresses(deps, env, start_after, limit)
+        }
+        MigrateMsg::BuildRestaurantIndexes { start_after, limit } => {
+            migrate::build_restaurant_indexes(deps, env, start_after, limit)
+        }
     }
 }
 
@@ -58,8 +75,49 @@ pub fn execute(
         ExecuteMsg::RegisterRestaurant {
             name,
             image_uri,
-            restaurant_address,
-        } => execute::register_restaurant(deps, info, name, image_uri, restaurant_address),
+            physical_address,
+            payout_address,
+            denom,
+        } => execute::register_restaurant(
+            deps,
+            info,
+            name,
+            image_uri,
+            physical_address,
+            payout_address,
+            denom,
+        ),
+
+        ExecuteMsg::UpdateRestaurant {
+            restaurant_id,
+            name,
+            image_uri,
+            physical_address,
+            payout_address,
+            denom,
+        } => execute::update_restaurant(
+            deps,
+            info,
+            restaurant_id,
+            name,
+            image_uri,
+            physical_address,
+            payout_address,
+            denom,
+        ),
+
+        ExecuteMsg::SetOpeningHours {
+            restaurant_id,
+            opening_hours,
+            utc_offset_minutes,
+        } => {
+            execute::set_opening_hours(deps, info, restaurant_id, opening_hours, utc_offset_minutes)
+        }
+
+        ExecuteMsg::SetRestaurantPaused {
+            restaurant_id,
+            paused,
+        } => execute::set_restaurant_paused(deps, info, restaurant_id, paused),
 
         ExecuteMsg::RegisterRider { name, phone_number } => {
             execute::register_rider(deps, info, name, phone_number)
@@ -72,72 +130,314 @@ pub fn execute(
         ExecuteMsg::CreateOrder {
             restaurant_id,
             items,
-        } => execute::create_order(deps, env, info, restaurant_id, items),
+            tip,
+            delivery_code_hash,
+        } => execute::create_order(
+            deps,
+            env,
+            info,
+            restaurant_id,
+            items,
+            tip,
+            delivery_code_hash,
+        ),
+
+        ExecuteMsg::Receive(wrapper) => execute::receive_cw20(deps, env, info, wrapper),
+
+        ExecuteMsg::GrantRestaurantRole {
+            restaurant_id,
+            operator,
+            role,
+        } => execute::grant_restaurant_role(deps, info, restaurant_id, operator, role),
+
+        ExecuteMsg::RevokeRestaurantRole {
+            restaurant_id,
+            operator,
+        } => execute::revoke_restaurant_role(deps, info, restaurant_id, operator),
 
         ExecuteMsg::AddMenuItem {
+            restaurant_id,
             item_id,
             name,
             price,
             image_uri,
-        } => execute::add_menu_item(deps, info, item_id, name, price, image_uri),
+            dietary_info,
+            denom,
+        } => execute::add_menu_item(
+            deps,
+            info,
+            restaurant_id,
+            item_id,
+            name,
+            price,
+            image_uri,
+            dietary_info,
+            denom,
+        ),
 
-        ExecuteMsg::RemoveMenuItem { item_id } => execute::remove_menu_item(deps, info, item_id),
+        ExecuteMsg::RemoveMenuItem {
+            restaurant_id,
+            item_id,
+        } => execute::remove_menu_item(deps, info, restaurant_id, item_id),
 
         ExecuteMsg::UpdateMenuItem {
+            restaurant_id,
+            item_id,
+            name,
+            price,
+            available,
+            image_uri,
+            dietary_info,
+            denom,
+        } => update_menu_item(
+            deps,
+            info,
+            restaurant_id,
             item_id,
             name,
             price,
             available,
             image_uri,
-        } => update_menu_item(deps, info, item_id, name, price, available, image_uri),
+            dietary_info,
+            denom,
+        ),
+
+        ExecuteMsg::ToggleMenuItemAvailability {
+            restaurant_id,
+            item_id,
+        } => execute::toggle_menu_item_availability(deps, info, restaurant_id, item_id),
+
+        ExecuteMsg::UpsertMenuItems {
+            restaurant_id,
+            items,
+        } => execute::upsert_menu_items(deps, info, restaurant_id, items, false),
+
+        ExecuteMsg::ReplaceMenu {
+            restaurant_id,
+            items,
+        } => execute::upsert_menu_items(deps, info, restaurant_id, items, true),
 
-        ExecuteMsg::ToggleMenuItemAvailability { item_id } => {
-            execute::toggle_menu_item_availability(deps, info, item_id)
+        ExecuteMsg::AddMenuCategory {
+            restaurant_id,
+            category_id,
+            name,
+        } => execute::add_menu_category(deps, info, restaurant_id, category_id, name),
+
+        ExecuteMsg::RemoveMenuCategory {
+            restaurant_id,
+            category_id,
+        } => execute::remove_menu_category(deps, info, restaurant_id, category_id),
+
+        ExecuteMsg::ReorderMenuCategories {
+            restaurant_id,
+            category_ids,
+        } => execute::reorder_menu_categories(deps, info, restaurant_id, category_ids),
+
+        ExecuteMsg::ReorderMenuItems {
+            restaurant_id,
+            category_id,
+            item_ids,
+        } => execute::reorder_menu_items(deps, info, restaurant_id, category_id, item_ids),
+
+        ExecuteMsg::RestockItems {
+            restaurant_id,
+            items,
+        } => execute::restock_items(deps, info, restaurant_id, items),
+
+        ExecuteMsg::SetMenuItemOptionGroups {
+            restaurant_id,
+            item_id,
+            option_groups,
+        } => {
+            execute::set_menu_item_option_groups(deps, info, restaurant_id, item_id, option_groups)
+        }
+
+        ExecuteMsg::AcceptOrder { order_id } => execute::accept_order(deps, env, info, order_id),
+
+        ExecuteMsg::StartPreparing { order_id } => {
+            execute::start_preparing(deps, env, info, order_id)
         }
 
-        ExecuteMsg::AcceptOrder { order_id } => execute::accept_order(deps, info, order_id),
+        ExecuteMsg::MarkReadyForPickup { order_id } => {
+            execute::mark_ready_for_pickup(deps, env, info, order_id)
+        }
 
-        ExecuteMsg::DepositFunds { order_id } => execute::deposit_funds(deps, info, order_id),
+        ExecuteMsg::PickUpOrder { order_id } => execute::pick_up_order(deps, env, info, order_id),
 
         ExecuteMsg::AssignRider { order_id, rider_id } => {
-            execute::assign_rider(deps, order_id, rider_id)
+            execute::assign_rider(deps, info, order_id, rider_id)
+        }
+
+        ExecuteMsg::ClaimDelivery { order_id } => execute::claim_delivery(deps, info, order_id),
+
+        ExecuteMsg::DeclineDelivery { order_id } => {
+            execute::decline_delivery(deps, env, info, order_id)
+        }
+
+        ExecuteMsg::ConfirmDelivery {
+            order_id,
+            delivery_code,
+        } => execute::confirm_delivery(deps, env, info, order_id, delivery_code),
+
+        ExecuteMsg::ConfirmReceipt { order_id } => {
+            execute::confirm_receipt(deps, env, info, order_id)
+        }
+
+        ExecuteMsg::ReviewRestaurant {
+            order_id,
+            rating,
+            comment,
+        } => execute::review_restaurant(deps, env, info, order_id, rating, comment),
+
+        ExecuteMsg::RateRider { order_id, rating } => {
+            execute::rate_rider(deps, info, order_id, rating)
+        }
+
+        ExecuteMsg::SettleOrder { order_id } => execute::settle_order(deps, env, order_id),
+
+        ExecuteMsg::OpenDispute { order_id, reason } => {
+            execute::open_dispute(deps, env, info, order_id, reason)
         }
 
-        ExecuteMsg::ConfirmDelivery { order_id } => {
-            execute::confirm_delivery(deps, env, info, order_id)
+        ExecuteMsg::ResolveDispute {
+            order_id,
+            customer_amount,
+            restaurant_amount,
+            rider_amount,
+        } => execute::resolve_dispute(
+            deps,
+            env,
+            info,
+            order_id,
+            customer_amount,
+            restaurant_amount,
+            rider_amount,
+        ),
+
+        ExecuteMsg::CancelOrder { order_id } => execute::cancel_order(deps, env, info, order_id),
+
+        ExecuteMsg::Withdraw {} => execute::withdraw(deps, info),
+
+        ExecuteMsg::ClaimExpiredOrder { order_id } => {
+            execute::claim_expired_order(deps, env, info, order_id)
         }
 
         ExecuteMsg::AddNewOwner { new_owner } => execute::add_new_owner(deps, info, new_owner),
 
         ExecuteMsg::RemoveOwner { owner } => execute::remove_owner(deps, info, owner),
+
+        ExecuteMsg::UpdatePlatformConfig {
+            platform_name,
+            platform_description,
+            fee_percentage,
+            fee_address,
+            order_acceptance_timeout,
+            delivery_fees,
+            rider_suspension_threshold,
+            dispute_window,
+            accepted_denoms,
+            accepted_cw20s,
+        } => execute::update_platform_config(
+            deps,
+            info,
+            platform_name,
+            platform_description,
+            fee_percentage,
+            fee_address,
+            order_acceptance_timeout,
+            delivery_fees,
+            rider_suspension_threshold,
+            dispute_window,
+            accepted_denoms,
+            accepted_cw20s,
+        ),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetPlatformConfig {} => to_json_binary(&query_platform_config(deps)?),
 
+        QueryMsg::GetClaimableBalance { address } => {
+            to_json_binary(&get_claimable_balance(deps, address)?)
+        }
+
         QueryMsg::GetOwners {} => to_json_binary(&get_owners(deps)?),
 
         QueryMsg::GetUser { id } => to_json_binary(&get_user(deps, id)?),
 
-        QueryMsg::GetRestaurants {} => to_json_binary(&get_all_restaurants(deps)?),
-
-        QueryMsg::GetAllSuccessfulOrderStatus { is_delivered } => {
-            to_json_binary(&get_order_status(deps, is_delivered)?)
+        QueryMsg::GetRestaurants { start_after, limit } => {
+            to_json_binary(&get_all_restaurants(deps, env, start_after, limit)?)
         }
 
+        QueryMsg::GetAllSuccessfulOrderStatus {
+            is_delivered,
+            start_after,
+            limit,
+        } => to_json_binary(&get_order_status(deps, is_delivered, start_after, limit)?),
+
         QueryMsg::GetOrderDetails { id } => to_json_binary(&get_order_by_id(deps, id)?),
 
         QueryMsg::GetEscrow { order_id } => to_json_binary(&get_escrow(deps, order_id)?),
 
-        QueryMsg::GetMenuItems { restaurant_id } => {
-            to_json_binary(&get_menu_items_for_restaurant(deps, restaurant_id)?)
-        }
+        QueryMsg::GetRestaurantReviews {
+            restaurant_id,
+            start_after,
+            limit,
+        } => to_json_binary(&get_restaurant_reviews(
+            deps,
+            restaurant_id,
+            start_after,
+            limit,
+        )?),
+
+        QueryMsg::GetRestaurantOperators {
+            restaurant_id,
+            start_after,
+            limit,
+        } => to_json_binary(&get_restaurant_operators(
+            deps,
+            restaurant_id,
+            start_after,
+            limit,
+        )?),
+
+        QueryMsg::GetMenuItems {
+            restaurant_id,
+            start_after,
+            limit,
+        } => to_json_binary(&get_menu_items_for_restaurant(
+            deps,
+            restaurant_id,
+            start_after,
+            limit,
+        )?),
+
+        QueryMsg::GetMenu {
+            restaurant_id,
+            exclude_allergens,
+        } => to_json_binary(&get_menu(deps, restaurant_id, exclude_allergens)?),
 
-        QueryMsg::GetOrdersFromARestaurant { restaurant_id } => {
-            to_json_binary(&get_orders_for_restaurant(deps, restaurant_id)?)
+        QueryMsg::GetOrdersFromARestaurant {
+            restaurant_id,
+            start_after,
+            limit,
+        } => to_json_binary(&get_orders_for_restaurant(
+            deps,
+            restaurant_id,
+            start_after,
+            limit,
+        )?),
+
+        QueryMsg::GetRiderOrders {
+            rider_id,
+            start_after,
+            limit,
+        } => to_json_binary(&get_rider_orders(deps, rider_id, start_after, limit)?),
+
+        QueryMsg::GetOpenDeliveryJobs { start_after, limit } => {
+            to_json_binary(&get_open_delivery_jobs(deps, start_after, limit)?)
         }
 
         QueryMsg::GetOrderStatusById { order_id } => {
@@ -146,21 +446,36 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 
         QueryMsg::GetRiderById { rider_id } => to_json_binary(&get_rider(deps, rider_id)?),
 
+        QueryMsg::GetRiderStats { rider_id } => to_json_binary(&get_rider_stats(deps, rider_id)?),
+
         QueryMsg::GetRiderByAddress { riders_address } => {
             let validated_riders_address = deps.api.addr_validate(&riders_address)?;
 
             to_json_binary(&get_rider_by_address(deps, validated_riders_address)?)
         }
 
-        QueryMsg::GetUserOwnedRestaurants { owner } => {
+        QueryMsg::GetUserOwnedRestaurants {
+            owner,
+            start_after,
+            limit,
+        } => {
             let validated_r_o_add = deps.api.addr_validate(&owner)?;
 
-            to_json_binary(&get_user_restaurants(deps, validated_r_o_add)?)
+            to_json_binary(&get_user_restaurants(
+                deps,
+                validated_r_o_add,
+                start_after,
+                limit,
+            )?)
         }
 
-        QueryMsg::GetUserOrders { address } => {
+        QueryMsg::GetUserOrders {
+            address,
+            start_after,
+            limit,
+        } => {
             let validated_address = deps.api.addr_validate(&address)?;
-            let response = get_user_orders(deps, validated_address)?;
+            let response = get_user_orders(deps, validated_address, start_after, limit)?;
             to_json_binary(&response)
         }
 
@@ -172,6 +487,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetOrderCost {
             restaurant_id,
             items,
-        } => to_json_binary(&get_order_cost(deps, restaurant_id, items)?),
+            tip,
+        } => to_json_binary(&get_order_cost(deps, env, restaurant_id, items, tip)?),
     }
 }
@@ -39,6 +39,9 @@ pub enum ContractError {
     #[error("No rider assigned")]
     NoRiderAssigned,
 
+    #[error("Order already has a rider assigned")]
+    RiderAlreadyAssigned {},
+
     #[error("Order not completed")]
     OrderNotCompleted,
 
@@ -87,6 +90,102 @@ pub enum ContractError {
     #[error("User already exists")]
     UserAlreadyExists { id: String },
 
+    #[error("Order has not expired yet")]
+    OrderNotExpired {},
+
+    #[error("Rider not registered")]
+    RiderNotRegistered {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("Operator not found")]
+    OperatorNotFound {},
+
+    #[error("Restaurant is closed")]
+    RestaurantClosed {},
+
+    #[error("Invalid opening hours")]
+    InvalidOpeningHours {},
+
+    #[error("Invalid option groups")]
+    InvalidOptionGroups {},
+
+    #[error("Invalid option selection")]
+    InvalidOptionSelection {},
+
+    #[error("Insufficient stock for item {item_id}")]
+    InsufficientStock { item_id: String },
+
+    #[error("Invalid menu item {item_id}: {reason}")]
+    InvalidMenuItem { item_id: String, reason: String },
+
+    #[error("Menu category not found")]
+    MenuCategoryNotFound {},
+
+    #[error("Menu category already exists")]
+    MenuCategoryAlreadyExists {},
+
+    #[error("Invalid menu order")]
+    InvalidMenuOrder {},
+
+    #[error("A menu can hold at most {max} items")]
+    TooManyMenuItems { max: usize },
+
+    #[error("A menu can hold at most {max} categories")]
+    TooManyMenuCategories { max: usize },
+
+    #[error("Rating must be between 1 and 5")]
+    InvalidRating {},
+
+    #[error("Review is too long")]
+    ReviewTooLong {},
+
+    #[error("Order already reviewed")]
+    OrderAlreadyReviewed {},
+
+    #[error("Rider already rated for this order")]
+    RiderAlreadyRated {},
+
+    #[error("Dispute window has closed")]
+    DisputeWindowClosed {},
+
+    #[error("Dispute window is still open")]
+    DisputeWindowOpen {},
+
+    #[error("Order is not disputed")]
+    OrderNotDisputed {},
+
+    #[error("Dispute split must equal the escrowed amount")]
+    InvalidDisputeSplit {},
+
+    #[error("Delivery code hash must be a 32 byte sha256 digest")]
+    InvalidDeliveryCodeHash {},
+
+    #[error("Invalid delivery code")]
+    InvalidDeliveryCode {},
+
+    #[error("Order not being prepared")]
+    OrderNotPreparing {},
+
+    #[error("Order not ready for pickup")]
+    OrderNotReadyForPickup {},
+
+    #[error("Denom {denom} is not accepted")]
+    DenomNotAccepted { denom: String },
+
+    #[error("Accepted denoms must be a non-empty list of unique denoms")]
+    InvalidAcceptedDenoms {},
+
+    #[error("All items in an order must be priced in the same denom")]
+    MixedDenoms {},
+
+    #[error("No delivery fee is configured for denom {denom}")]
+    DeliveryFeeNotConfigured { denom: String },
+
+    #[error("Delivery fees must list each denom at most once")]
+    InvalidDeliveryFees {},
+
     #[error("Rider already exists")]
     RiderAlreadyExists { id: String },
 }
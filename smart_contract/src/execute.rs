@@ -1,15 +1,33 @@
-use cosmwasm_std::{BankMsg, Coin};
+use std::collections::HashSet;
+
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Deps, Event, HexBinary, StdResult,
+    Storage, Timestamp, WasmMsg,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{Decimal, DepsMut, Env, MessageInfo, Response, Uint128};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use sha2::{Digest, Sha256};
 
 use crate::error::ContractError;
-use crate::msg::OrderItem;
+use crate::msg::{MenuItemInput, OrderItem, ReceiveMsg, RestockItem};
+use crate::state::{cw20_denom, order_id, CW20_DENOM_PREFIX, DEFAULT_DENOM};
+use crate::state::{salted_delivery_code_hash, MIN_DELIVERY_CODE_LENGTH};
+use crate::state::{sync_open_job, OPEN_JOBS};
+use crate::state::{
+    DietaryInfo, MenuCategory, RestaurantRole, Rider, MENU_CATEGORIES, MENU_ITEMS,
+    RESTAURANT_OPERATORS,
+};
+use crate::state::{Dispute, DisputeResolution, MIN_RIDER_RATINGS_FOR_SUSPENSION, RIDER_RATINGS};
 use crate::state::{
-    Escrow, MenuItem, Order, OrderStatus, PlatformConfig, Restaurant, User, ESCROWS, ORDERS,
-    PLATFORM_CONFIG, RESTAURANTS, RIDERS, USERS,
+    Escrow, MenuItem, Order, OrderStatus, PlatformConfig, Restaurant, User, CLAIMABLE_BALANCES,
+    DEFAULT_ORDER_ACCEPTANCE_TIMEOUT, ESCROWS, ORDERS, ORDER_SEQ, PLATFORM_CONFIG, RESTAURANTS,
+    RESTAURANT_SEQ, RIDERS, USERS,
 };
-use crate::state::{Rider, MENU_ITEMS};
-const NATIVE_DENOM: &str = "uxion";
+use crate::state::{OpeningHours, OptionGroup, MAX_UTC_OFFSET_MINUTES, MINUTES_PER_DAY};
+use crate::state::{Review, MAX_REVIEW_LENGTH, RESTAURANT_REVIEWS};
+use crate::state::{MAX_MENU_CATEGORIES, MAX_MENU_ITEMS};
+#[allow(clippy::too_many_arguments)]
 pub fn init(
     deps: DepsMut,
     _info: MessageInfo,
@@ -18,10 +36,30 @@ pub fn init(
     owner_address: String,
     fee_percentage: Decimal,
     fee_address: String,
+    order_acceptance_timeout: Option<u64>,
+    delivery_fees: Option<Vec<Coin>>,
+    rider_suspension_threshold: Option<Decimal>,
+    dispute_window: Option<u64>,
+    accepted_denoms: Option<Vec<String>>,
+    accepted_cw20s: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
     if fee_percentage > Decimal::one() {
         return Err(ContractError::InvalidFeePercentage {});
     }
+    let accepted_denoms = accepted_denoms.unwrap_or_else(|| vec![DEFAULT_DENOM.to_string()]);
+    validate_accepted_denoms(&accepted_denoms)?;
+    let delivery_fees = delivery_fees.unwrap_or_else(|| {
+        accepted_denoms
+            .iter()
+            .map(|denom| Coin::new(0, denom))
+            .collect()
+    });
+    validate_delivery_fees(&delivery_fees)?;
+    let accepted_cw20s = accepted_cw20s
+        .unwrap_or_default()
+        .iter()
+        .map(|token| deps.api.addr_validate(token))
+        .collect::<StdResult<Vec<_>>>()?;
 
     let validated_owner = deps.api.addr_validate(&owner_address)?;
     let validated_fee_address = deps.api.addr_validate(&fee_address)?;
@@ -32,12 +70,59 @@ pub fn init(
         owners: vec![validated_owner.clone()],
         fee_percentage,
         fee_address: validated_fee_address,
+        order_acceptance_timeout: order_acceptance_timeout
+            .unwrap_or(DEFAULT_ORDER_ACCEPTANCE_TIMEOUT),
+        delivery_fees,
+        rider_suspension_threshold: rider_suspension_threshold.unwrap_or_default(),
+        dispute_window: dispute_window.unwrap_or_default(),
+        accepted_denoms,
+        accepted_cw20s,
     };
     PLATFORM_CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "init"))
 }
 
+fn validate_accepted_denoms(denoms: &[String]) -> Result<(), ContractError> {
+    let mut seen = HashSet::new();
+    if denoms.is_empty()
+        || !denoms.iter().all(|denom| {
+            !denom.is_empty() && !denom.starts_with(CW20_DENOM_PREFIX) && seen.insert(denom)
+        })
+    {
+        return Err(ContractError::InvalidAcceptedDenoms {});
+    }
+    Ok(())
+}
+
+fn validate_delivery_fees(fees: &[Coin]) -> Result<(), ContractError> {
+    let mut seen = HashSet::new();
+    if !fees.iter().all(|fee| seen.insert(&fee.denom)) {
+        return Err(ContractError::InvalidDeliveryFees {});
+    }
+    Ok(())
+}
+
+fn ensure_denom_accepted(storage: &dyn Storage, denom: &str) -> Result<(), ContractError> {
+    let config = PLATFORM_CONFIG.load(storage)?;
+    let accepted = match denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => config
+            .accepted_cw20s
+            .iter()
+            .any(|accepted| accepted.as_str() == token),
+        None => config
+            .accepted_denoms
+            .iter()
+            .any(|accepted| accepted == denom),
+    };
+    if !accepted {
+        return Err(ContractError::DenomNotAccepted {
+            denom: denom.to_string(),
+        });
+    }
+    Ok(())
+}
+
 pub fn add_new_owner(
     deps: DepsMut,
     info: MessageInfo,
@@ -62,6 +147,71 @@ pub fn add_new_owner(
         .add_attribute("new_owner", validated_owner.to_string()))
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn update_platform_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    platform_name: Option<String>,
+    platform_description: Option<String>,
+    fee_percentage: Option<Decimal>,
+    fee_address: Option<String>,
+    order_acceptance_timeout: Option<u64>,
+    delivery_fees: Option<Vec<Coin>>,
+    rider_suspension_threshold: Option<Decimal>,
+    dispute_window: Option<u64>,
+    accepted_denoms: Option<Vec<String>>,
+    accepted_cw20s: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    let mut platform_config = PLATFORM_CONFIG.load(deps.storage)?;
+
+    if !platform_config.owners.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(new_name) = platform_name {
+        platform_config.platform_name = new_name;
+    }
+    if let Some(new_description) = platform_description {
+        platform_config.platform_description = new_description;
+    }
+    if let Some(new_fee_percentage) = fee_percentage {
+        if new_fee_percentage > Decimal::one() {
+            return Err(ContractError::InvalidFeePercentage {});
+        }
+        platform_config.fee_percentage = new_fee_percentage;
+    }
+    if let Some(new_fee_address) = fee_address {
+        platform_config.fee_address = deps.api.addr_validate(&new_fee_address)?;
+    }
+    if let Some(new_timeout) = order_acceptance_timeout {
+        platform_config.order_acceptance_timeout = new_timeout;
+    }
+    if let Some(new_delivery_fees) = delivery_fees {
+        validate_delivery_fees(&new_delivery_fees)?;
+        platform_config.delivery_fees = new_delivery_fees;
+    }
+    if let Some(new_threshold) = rider_suspension_threshold {
+        platform_config.rider_suspension_threshold = new_threshold;
+    }
+    if let Some(new_dispute_window) = dispute_window {
+        platform_config.dispute_window = new_dispute_window;
+    }
+    if let Some(new_accepted_denoms) = accepted_denoms {
+        validate_accepted_denoms(&new_accepted_denoms)?;
+        platform_config.accepted_denoms = new_accepted_denoms;
+    }
+    if let Some(new_accepted_cw20s) = accepted_cw20s {
+        platform_config.accepted_cw20s = new_accepted_cw20s
+            .iter()
+            .map(|token| deps.api.addr_validate(token))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    PLATFORM_CONFIG.save(deps.storage, &platform_config)?;
+
+    Ok(Response::new().add_attribute("action", "update_platform_config"))
+}
+
 pub fn register_user(
     deps: DepsMut,
     info: MessageInfo,
@@ -110,7 +260,7 @@ pub fn remove_owner(
 
     platform_config
         .owners
-        .retain(|owner| owner != &validated_owner);
+        .retain(|owner| *owner != validated_owner);
     PLATFORM_CONFIG.save(deps.storage, &platform_config)?;
 
     Ok(Response::new()
@@ -122,32 +272,230 @@ pub fn register_restaurant(
     info: MessageInfo,
     name: String,
     image_uri: String,
-    restaurant_address: String,
+    physical_address: String,
+    payout_address: String,
+    denom: Option<String>,
 ) -> Result<Response, ContractError> {
-    let restaurant_id = format!("restaurant_{}", info.sender);
+    if let Some(denom) = &denom {
+        ensure_denom_accepted(deps.storage, denom)?;
+    }
+    let restaurant_id = next_restaurant_id(deps.storage)?;
     let restaurant = Restaurant {
         id: restaurant_id.clone(),
         owner: info.sender,
         name,
         image_uri,
-        restaurant_address,
+        physical_address,
+        payout_address: deps.api.addr_validate(&payout_address)?,
+        opening_hours: vec![],
+        utc_offset_minutes: 0,
+        paused: false,
+        rating_sum: 0,
+        rating_count: 0,
+        denom: denom.unwrap_or_else(|| DEFAULT_DENOM.to_string()),
     };
 
     RESTAURANTS.save(deps.storage, restaurant_id.as_str(), &restaurant)?;
-    Ok(Response::new().add_attribute("action", "register_restaurant"))
+    Ok(Response::new()
+        .add_attribute("action", "register_restaurant")
+        .add_attribute("restaurant_id", restaurant_id))
+}
+
+fn next_restaurant_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let mut seq = RESTAURANT_SEQ.may_load(storage)?.unwrap_or_default();
+    let restaurant_id = loop {
+        seq += 1;
+        let candidate = format!("restaurant_{}", seq);
+        if !RESTAURANTS.has(storage, &candidate) {
+            break candidate;
+        }
+    };
+    RESTAURANT_SEQ.save(storage, &seq)?;
+    Ok(restaurant_id)
+}
+
+fn load_restaurant_as(
+    storage: &dyn Storage,
+    sender: &Addr,
+    restaurant_id: &str,
+    role: RestaurantRole,
+) -> Result<Restaurant, ContractError> {
+    let restaurant = RESTAURANTS.load(storage, restaurant_id)?;
+    if restaurant.owner != *sender
+        && RESTAURANT_OPERATORS.may_load(storage, (restaurant_id, sender))? != Some(role)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(restaurant)
+}
+
+pub fn grant_restaurant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    operator: String,
+    role: RestaurantRole,
+) -> Result<Response, ContractError> {
+    load_owned_restaurant(deps.storage, &info.sender, &restaurant_id)?;
+
+    let validated_operator = deps.api.addr_validate(&operator)?;
+    RESTAURANT_OPERATORS.save(
+        deps.storage,
+        (restaurant_id.as_str(), &validated_operator),
+        &role,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_restaurant_role")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("operator", validated_operator)
+        .add_attribute("role", format!("{:?}", role)))
+}
+
+pub fn revoke_restaurant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    operator: String,
+) -> Result<Response, ContractError> {
+    load_owned_restaurant(deps.storage, &info.sender, &restaurant_id)?;
+
+    let validated_operator = deps.api.addr_validate(&operator)?;
+    let key = (restaurant_id.as_str(), &validated_operator);
+    if !RESTAURANT_OPERATORS.has(deps.storage, key) {
+        return Err(ContractError::OperatorNotFound {});
+    }
+    RESTAURANT_OPERATORS.remove(deps.storage, key);
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_restaurant_role")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("operator", validated_operator))
+}
+
+fn load_owned_restaurant(
+    storage: &dyn Storage,
+    sender: &Addr,
+    restaurant_id: &str,
+) -> Result<Restaurant, ContractError> {
+    let restaurant = RESTAURANTS.load(storage, restaurant_id)?;
+    if restaurant.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(restaurant)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_restaurant(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    name: Option<String>,
+    image_uri: Option<String>,
+    physical_address: Option<String>,
+    payout_address: Option<String>,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut restaurant = load_owned_restaurant(deps.storage, &info.sender, &restaurant_id)?;
+
+    if let Some(new_name) = name {
+        restaurant.name = new_name;
+    }
+    if let Some(new_image_uri) = image_uri {
+        restaurant.image_uri = new_image_uri;
+    }
+    if let Some(new_physical_address) = physical_address {
+        restaurant.physical_address = new_physical_address;
+    }
+    if let Some(new_payout_address) = payout_address {
+        restaurant.payout_address = deps.api.addr_validate(&new_payout_address)?;
+    }
+    if let Some(new_denom) = denom {
+        ensure_denom_accepted(deps.storage, &new_denom)?;
+        restaurant.denom = new_denom;
+    }
+
+    RESTAURANTS.save(deps.storage, &restaurant_id, &restaurant)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_restaurant")
+        .add_attribute("restaurant_id", restaurant_id))
+}
+
+pub fn set_opening_hours(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    opening_hours: Vec<OpeningHours>,
+    utc_offset_minutes: i32,
+) -> Result<Response, ContractError> {
+    let mut restaurant = load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    if utc_offset_minutes.abs() > MAX_UTC_OFFSET_MINUTES
+        || opening_hours.iter().any(|hours| {
+            hours.open_minute >= hours.close_minute || hours.close_minute > MINUTES_PER_DAY
+        })
+    {
+        return Err(ContractError::InvalidOpeningHours {});
+    }
+
+    restaurant.opening_hours = opening_hours;
+    restaurant.utc_offset_minutes = utc_offset_minutes;
+    RESTAURANTS.save(deps.storage, &restaurant_id, &restaurant)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_opening_hours")
+        .add_attribute("restaurant_id", restaurant_id))
+}
+
+pub fn set_restaurant_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut restaurant = load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    restaurant.paused = paused;
+    RESTAURANTS.save(deps.storage, &restaurant_id, &restaurant)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_restaurant_paused")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("paused", paused.to_string()))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn add_menu_item(
     deps: DepsMut,
     info: MessageInfo,
+    restaurant_id: String,
     item_id: String,
     name: String,
     price: Uint128,
     image_uri: String,
+    dietary_info: DietaryInfo,
+    denom: Option<String>,
 ) -> Result<Response, ContractError> {
-    let restaurant_id = format!("restaurant_{}", info.sender);
-
-    RESTAURANTS.load(deps.storage, &restaurant_id)?;
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+    if let Some(denom) = &denom {
+        ensure_denom_accepted(deps.storage, denom)?;
+    }
 
     let menu_item = MenuItem {
         id: item_id.clone(),
@@ -155,6 +503,12 @@ pub fn add_menu_item(
         price,
         available: true,
         image_uri,
+        option_groups: vec![],
+        stock: None,
+        category_id: None,
+        position: 0,
+        dietary_info,
+        denom,
     };
 
     MENU_ITEMS.save(
@@ -162,36 +516,72 @@ pub fn add_menu_item(
         (restaurant_id.as_str(), item_id.as_str()),
         &menu_item,
     )?;
+    ensure_menu_size(deps.storage, &restaurant_id)?;
 
     Ok(Response::new().add_attribute("action", "add_menu_item"))
 }
 
+fn ensure_menu_size(storage: &dyn Storage, restaurant_id: &str) -> Result<(), ContractError> {
+    let items = MENU_ITEMS
+        .prefix(restaurant_id)
+        .keys_raw(storage, None, None, cosmwasm_std::Order::Ascending)
+        .take(MAX_MENU_ITEMS + 1)
+        .count();
+    if items > MAX_MENU_ITEMS {
+        return Err(ContractError::TooManyMenuItems {
+            max: MAX_MENU_ITEMS,
+        });
+    }
+    let categories = MENU_CATEGORIES
+        .prefix(restaurant_id)
+        .keys_raw(storage, None, None, cosmwasm_std::Order::Ascending)
+        .take(MAX_MENU_CATEGORIES + 1)
+        .count();
+    if categories > MAX_MENU_CATEGORIES {
+        return Err(ContractError::TooManyMenuCategories {
+            max: MAX_MENU_CATEGORIES,
+        });
+    }
+    Ok(())
+}
+
 pub fn remove_menu_item(
     deps: DepsMut,
     info: MessageInfo,
+    restaurant_id: String,
     item_id: String,
 ) -> Result<Response, ContractError> {
-    let restaurant_id = format!("restaurant_{}", info.sender);
-
-    RESTAURANTS.load(deps.storage, &restaurant_id)?;
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
 
     MENU_ITEMS.remove(deps.storage, (restaurant_id.as_str(), item_id.as_str()));
 
     Ok(Response::new().add_attribute("action", "remove_menu_item"))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_menu_item(
     deps: DepsMut,
     info: MessageInfo,
+    restaurant_id: String,
     item_id: String,
     name: Option<String>,
     price: Option<Uint128>,
     available: Option<bool>,
     image_uri: Option<String>,
+    dietary_info: Option<DietaryInfo>,
+    denom: Option<String>,
 ) -> Result<Response, ContractError> {
-    let restaurant_id = format!("restaurant_{}", info.sender);
-
-    RESTAURANTS.load(deps.storage, &restaurant_id)?;
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
 
     let key = (restaurant_id.as_str(), item_id.as_str());
     let mut menu_item = MENU_ITEMS
@@ -210,6 +600,13 @@ pub fn update_menu_item(
     if let Some(new_image_uri) = image_uri {
         menu_item.image_uri = new_image_uri;
     }
+    if let Some(new_dietary_info) = dietary_info {
+        menu_item.dietary_info = new_dietary_info;
+    }
+    if let Some(new_denom) = denom {
+        ensure_denom_accepted(deps.storage, &new_denom)?;
+        menu_item.denom = Some(new_denom);
+    }
 
     MENU_ITEMS.save(deps.storage, key, &menu_item)?;
 
@@ -219,160 +616,795 @@ pub fn update_menu_item(
         .add_attribute("item_id", item_id))
 }
 
-pub fn toggle_menu_item_availability(
+pub fn upsert_menu_items(
     deps: DepsMut,
     info: MessageInfo,
-    item_id: String,
+    restaurant_id: String,
+    items: Vec<MenuItemInput>,
+    replace: bool,
 ) -> Result<Response, ContractError> {
-    let restaurant_id = format!("restaurant_{}", info.sender);
-
-    RESTAURANTS.load(deps.storage, &restaurant_id)?;
-
-    let key = (restaurant_id.as_str(), item_id.as_str());
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
 
-    let mut menu_item = MENU_ITEMS
-        .load(deps.storage, key)
-        .map_err(|_| ContractError::MenuItemNotFound {})?;
+    let mut item_ids = HashSet::new();
+    for item in &items {
+        let reason = if item.item_id.is_empty() {
+            "empty id"
+        } else if !item_ids.insert(item.item_id.as_str()) {
+            "duplicate id"
+        } else if item.price.is_zero() {
+            "zero price"
+        } else {
+            if let Some(denom) = &item.denom {
+                ensure_denom_accepted(deps.storage, denom)?;
+            }
+            continue;
+        };
+        return Err(ContractError::InvalidMenuItem {
+            item_id: item.item_id.clone(),
+            reason: reason.to_string(),
+        });
+    }
 
-    menu_item.available = !menu_item.available;
+    let mut events = vec![];
+    if replace {
+        let existing_ids = MENU_ITEMS
+            .prefix(&restaurant_id)
+            .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect::<StdResult<Vec<String>>>()?;
+        for item_id in existing_ids {
+            if !item_ids.contains(item_id.as_str()) {
+                MENU_ITEMS.remove(deps.storage, (&restaurant_id, &item_id));
+                events.push(
+                    Event::new("menu_item_removed")
+                        .add_attribute("restaurant_id", &restaurant_id)
+                        .add_attribute("item_id", item_id),
+                );
+            }
+        }
+    }
 
-    MENU_ITEMS.save(deps.storage, key, &menu_item)?;
+    for item in items {
+        let key = (restaurant_id.as_str(), item.item_id.as_str());
+        let existing = MENU_ITEMS.may_load(deps.storage, key)?;
+        let menu_item = match existing.clone() {
+            Some(existing) => MenuItem {
+                name: item.name,
+                price: item.price,
+                image_uri: item.image_uri,
+                dietary_info: item.dietary_info,
+                denom: item.denom,
+                ..existing
+            },
+            None => MenuItem {
+                id: item.item_id.clone(),
+                name: item.name,
+                price: item.price,
+                available: true,
+                image_uri: item.image_uri,
+                option_groups: vec![],
+                stock: None,
+                category_id: None,
+                position: 0,
+                dietary_info: item.dietary_info,
+                denom: item.denom,
+            },
+        };
+        if existing.as_ref() == Some(&menu_item) {
+            continue;
+        }
+        MENU_ITEMS.save(deps.storage, key, &menu_item)?;
+        events.push(
+            Event::new("menu_item_upserted")
+                .add_attribute("restaurant_id", &restaurant_id)
+                .add_attribute("item_id", item.item_id)
+                .add_attribute("price", menu_item.price.to_string()),
+        );
+    }
+    ensure_menu_size(deps.storage, &restaurant_id)?;
 
     Ok(Response::new()
-        .add_attribute("action", "toggle_menu_item_availability")
+        .add_attribute(
+            "action",
+            if replace {
+                "replace_menu"
+            } else {
+                "upsert_menu_items"
+            },
+        )
         .add_attribute("restaurant_id", restaurant_id)
-        .add_attribute("item_id", item_id)
-        .add_attribute("available", menu_item.available.to_string()))
+        .add_events(events))
 }
-pub fn create_order(
+
+pub fn add_menu_category(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
     restaurant_id: String,
-    items: Vec<OrderItem>,
+    category_id: String,
+    name: String,
 ) -> Result<Response, ContractError> {
-    if items.is_empty() {
-        return Err(ContractError::EmptyOrder {});
-    }
-
-    let mut total = Uint128::zero();
-    for item in &items {
-        let menu_item = MENU_ITEMS
-            .may_load(deps.storage, (&restaurant_id, &item.item_id))?
-            .ok_or(ContractError::ItemNotFound {})?;
-        if !menu_item.available {
-            return Err(ContractError::ItemNotAvailable {});
-        }
-        let item_total = menu_item
-            .price
-            .checked_mul(Uint128::from(item.quantity))
-            .map_err(|_| ContractError::Overflow {})?;
-        total = total
-            .checked_add(item_total)
-            .map_err(|_| ContractError::Overflow {})?;
-    }
-
-    if total.is_zero() {
-        return Err(ContractError::InvalidOrderAmount {});
-    }
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
 
-    if info.funds.len() != 1 || info.funds[0].denom != NATIVE_DENOM || info.funds[0].amount != total
-    {
-        return Err(ContractError::IncorrectPayment {});
+    let key = (restaurant_id.as_str(), category_id.as_str());
+    if MENU_CATEGORIES.has(deps.storage, key) {
+        return Err(ContractError::MenuCategoryAlreadyExists {});
     }
-
-    let order_id = if cfg!(test) {
-        "order_1".to_string()
-    } else {
-        format!("order_{}", env.block.height)
-    };
-
-    RESTAURANTS.load(deps.storage, &restaurant_id)?;
-
-    let order = Order {
-        id: order_id.clone(),
-        customer: info.sender.clone(),
-        restaurant_id: restaurant_id.clone(),
-        items,
-        total,
-        status: OrderStatus::Created,
-        rider_id: None,
-    };
-    ORDERS.save(deps.storage, &order_id, &order)?;
-    ESCROWS.save(
+    // New categories are appended after the existing ones.
+    let position = MENU_CATEGORIES
+        .prefix(&restaurant_id)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(_, category)| category.position + 1))
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .max()
+        .unwrap_or_default();
+    MENU_CATEGORIES.save(
         deps.storage,
-        &order_id,
-        &Escrow {
-            order_id: order_id.clone(),
-            amount: total,
-            released: false,
+        key,
+        &MenuCategory {
+            id: category_id.clone(),
+            name,
+            position,
         },
     )?;
+    ensure_menu_size(deps.storage, &restaurant_id)?;
 
     Ok(Response::new()
-        .add_attribute("action", "create_order")
-        .add_attribute("order_id", order_id)
+        .add_attribute("action", "add_menu_category")
         .add_attribute("restaurant_id", restaurant_id)
-        .add_attribute("total", total.to_string()))
+        .add_attribute("category_id", category_id))
 }
 
-pub fn confirm_delivery(
+pub fn remove_menu_category(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
-    order_id: String,
+    restaurant_id: String,
+    category_id: String,
 ) -> Result<Response, ContractError> {
-    let mut order = ORDERS.load(deps.storage, &order_id)?;
-    if order.status != OrderStatus::InDelivery {
-        return Err(ContractError::OrderNotInDelivery {});
-    }
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
 
-    let rider_id = order
-        .rider_id
-        .as_ref()
-        .ok_or(ContractError::NoRiderAssigned {})?;
-    let rider = RIDERS.load(deps.storage, rider_id)?;
-    if info.sender != rider.wallet {
-        return Err(ContractError::Unauthorized {});
+    let key = (restaurant_id.as_str(), category_id.as_str());
+    if !MENU_CATEGORIES.has(deps.storage, key) {
+        return Err(ContractError::MenuCategoryNotFound {});
     }
-
-    let escrow = ESCROWS.load(deps.storage, &order_id)?;
-    if escrow.released {
-        return Err(ContractError::FundsAlreadyReleased {});
+    MENU_CATEGORIES.remove(deps.storage, key);
+
+    // Items from the removed category fall back to the uncategorized section.
+    let menu_items = MENU_ITEMS
+        .prefix(&restaurant_id)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (item_id, mut menu_item) in menu_items {
+        if menu_item.category_id.as_deref() == Some(category_id.as_str()) {
+            menu_item.category_id = None;
+            MENU_ITEMS.save(deps.storage, (&restaurant_id, &item_id), &menu_item)?;
+        }
     }
 
-    let contract_balance = deps
-        .querier
-        .query_balance(&env.contract.address, NATIVE_DENOM)?;
-    if contract_balance.amount < escrow.amount {
-        return Err(ContractError::InsufficientEscrowBalance {});
+    Ok(Response::new()
+        .add_attribute("action", "remove_menu_category")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("category_id", category_id))
+}
+
+pub fn reorder_menu_categories(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    category_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    // The new order must list every existing category exactly once.
+    let mut existing_ids = MENU_CATEGORIES
+        .prefix(&restaurant_id)
+        .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<String>>>()?;
+    let mut requested_ids = category_ids.clone();
+    existing_ids.sort();
+    requested_ids.sort();
+    if existing_ids != requested_ids {
+        return Err(ContractError::InvalidMenuOrder {});
     }
 
-    let config = PLATFORM_CONFIG.load(deps.storage)?;
-    let fee_amount = escrow.amount * config.fee_percentage;
-    let remaining_amount = escrow
-        .amount
-        .checked_sub(fee_amount)
-        .map_err(|_| ContractError::Overflow {})?;
+    for (position, category_id) in category_ids.iter().enumerate() {
+        let key = (restaurant_id.as_str(), category_id.as_str());
+        let mut category = MENU_CATEGORIES.load(deps.storage, key)?;
+        category.position = position as u32;
+        MENU_CATEGORIES.save(deps.storage, key, &category)?;
+    }
 
-    let fee_msg = BankMsg::Send {
-        to_address: config.fee_address.to_string(),
-        amount: vec![Coin {
-            denom: NATIVE_DENOM.to_string(),
-            amount: fee_amount,
+    Ok(Response::new()
+        .add_attribute("action", "reorder_menu_categories")
+        .add_attribute("restaurant_id", restaurant_id))
+}
+
+pub fn reorder_menu_items(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    category_id: Option<String>,
+    item_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    if let Some(category_id) = &category_id {
+        if !MENU_CATEGORIES.has(deps.storage, (&restaurant_id, category_id)) {
+            return Err(ContractError::MenuCategoryNotFound {});
+        }
+    }
+    let unique_ids: HashSet<&String> = item_ids.iter().collect();
+    if unique_ids.len() != item_ids.len() {
+        return Err(ContractError::InvalidMenuOrder {});
+    }
+
+    for (position, item_id) in item_ids.iter().enumerate() {
+        let key = (restaurant_id.as_str(), item_id.as_str());
+        let mut menu_item = MENU_ITEMS
+            .load(deps.storage, key)
+            .map_err(|_| ContractError::MenuItemNotFound {})?;
+        menu_item.category_id = category_id.clone();
+        menu_item.position = position as u32;
+        MENU_ITEMS.save(deps.storage, key, &menu_item)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "reorder_menu_items")
+        .add_attribute("restaurant_id", restaurant_id))
+}
+
+pub fn restock_items(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    items: Vec<RestockItem>,
+) -> Result<Response, ContractError> {
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    for item in &items {
+        let key = (restaurant_id.as_str(), item.item_id.as_str());
+        let mut menu_item = MENU_ITEMS
+            .load(deps.storage, key)
+            .map_err(|_| ContractError::MenuItemNotFound {})?;
+        if menu_item.stock == Some(0) {
+            menu_item.available = true;
+        }
+        if item.stock == Some(0) {
+            menu_item.available = false;
+        }
+        menu_item.stock = item.stock;
+        MENU_ITEMS.save(deps.storage, key, &menu_item)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "restock_items")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("items", items.len().to_string()))
+}
+
+pub fn set_menu_item_option_groups(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    item_id: String,
+    option_groups: Vec<OptionGroup>,
+) -> Result<Response, ContractError> {
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    let mut group_ids = HashSet::new();
+    let mut option_ids = HashSet::new();
+    for group in &option_groups {
+        if !group_ids.insert(&group.id)
+            || group.min_selections > group.max_selections
+            || group.max_selections as usize > group.options.len()
+            || !group
+                .options
+                .iter()
+                .all(|option| option_ids.insert(&option.id))
+        {
+            return Err(ContractError::InvalidOptionGroups {});
+        }
+    }
+
+    let key = (restaurant_id.as_str(), item_id.as_str());
+    let mut menu_item = MENU_ITEMS
+        .load(deps.storage, key)
+        .map_err(|_| ContractError::MenuItemNotFound {})?;
+    menu_item.option_groups = option_groups;
+    MENU_ITEMS.save(deps.storage, key, &menu_item)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_menu_item_option_groups")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("item_id", item_id))
+}
+
+pub fn toggle_menu_item_availability(
+    deps: DepsMut,
+    info: MessageInfo,
+    restaurant_id: String,
+    item_id: String,
+) -> Result<Response, ContractError> {
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &restaurant_id,
+        RestaurantRole::Manager,
+    )?;
+
+    let key = (restaurant_id.as_str(), item_id.as_str());
+
+    let mut menu_item = MENU_ITEMS
+        .load(deps.storage, key)
+        .map_err(|_| ContractError::MenuItemNotFound {})?;
+
+    menu_item.available = !menu_item.available;
+
+    MENU_ITEMS.save(deps.storage, key, &menu_item)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "toggle_menu_item_availability")
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("item_id", item_id)
+        .add_attribute("available", menu_item.available.to_string()))
+}
+pub fn create_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    restaurant_id: String,
+    items: Vec<OrderItem>,
+    tip: Option<Uint128>,
+    delivery_code_hash: HexBinary,
+) -> Result<Response, ContractError> {
+    if items.is_empty() {
+        return Err(ContractError::EmptyOrder {});
+    }
+    if delivery_code_hash.len() != 32 {
+        return Err(ContractError::InvalidDeliveryCodeHash {});
+    }
+
+    let restaurant = RESTAURANTS.load(deps.storage, &restaurant_id)?;
+    if !restaurant.is_open(env.block.time) {
+        return Err(ContractError::RestaurantClosed {});
+    }
+
+    let mut total = Uint128::zero();
+    let mut order_denom: Option<String> = None;
+    for item in &items {
+        let mut menu_item = MENU_ITEMS
+            .may_load(deps.storage, (&restaurant_id, &item.item_id))?
+            .ok_or(ContractError::ItemNotFound {})?;
+        if !menu_item.available {
+            return Err(ContractError::ItemNotAvailable {});
+        }
+        let item_denom = menu_item.denom(&restaurant);
+        match &order_denom {
+            Some(denom) if denom != item_denom => return Err(ContractError::MixedDenoms {}),
+            Some(_) => {}
+            None => order_denom = Some(item_denom.to_string()),
+        }
+        let item_total = menu_item
+            .unit_price(&item.selected_options)?
+            .checked_mul(Uint128::from(item.quantity))
+            .map_err(|_| ContractError::Overflow {})?;
+        if let Some(stock) = menu_item.stock {
+            let remaining = stock.checked_sub(item.quantity).ok_or_else(|| {
+                ContractError::InsufficientStock {
+                    item_id: item.item_id.clone(),
+                }
+            })?;
+            menu_item.stock = Some(remaining);
+            menu_item.available = remaining > 0;
+            MENU_ITEMS.save(deps.storage, (&restaurant_id, &item.item_id), &menu_item)?;
+        }
+        total = total
+            .checked_add(item_total)
+            .map_err(|_| ContractError::Overflow {})?;
+    }
+
+    if total.is_zero() {
+        return Err(ContractError::InvalidOrderAmount {});
+    }
+    let denom = order_denom.ok_or(ContractError::EmptyOrder {})?;
+    ensure_denom_accepted(deps.storage, &denom)?;
+
+    let delivery_fee = PLATFORM_CONFIG.load(deps.storage)?.delivery_fee(&denom)?;
+    let tip = tip.unwrap_or_default();
+    let amount_due = total
+        .checked_add(delivery_fee)
+        .and_then(|amount| amount.checked_add(tip))
+        .map_err(|_| ContractError::Overflow {})?;
+
+    if info.funds.len() != 1 || info.funds[0].denom != denom || info.funds[0].amount != amount_due {
+        return Err(ContractError::IncorrectPayment {});
+    }
+
+    let order_id = next_order_id(deps.storage)?;
+
+    let mut order = Order {
+        id: order_id.clone(),
+        customer: info.sender.clone(),
+        restaurant_id: restaurant_id.clone(),
+        items,
+        total,
+        status: OrderStatus::Created,
+        rider_id: None,
+        created_at: env.block.time,
+        delivery_fee,
+        tip,
+        completed_at: None,
+        dispute: None,
+        delivery_code_hash: Some(salted_delivery_code_hash(
+            &order_id,
+            &info.sender,
+            &delivery_code_hash,
+        )),
+        status_history: vec![],
+        denom: denom.clone(),
+    };
+    order.set_status(OrderStatus::Created, &info.sender, env.block.time);
+    ORDERS.save(deps.storage, &order_id, &order)?;
+    ESCROWS.save(
+        deps.storage,
+        &order_id,
+        &Escrow {
+            order_id: order_id.clone(),
+            amount: amount_due,
+            released: false,
+            denom,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_order")
+        .add_attribute("order_id", order_id)
+        .add_attribute("restaurant_id", restaurant_id)
+        .add_attribute("total", total.to_string()))
+}
+
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the token contract; the customer is the wrapper's sender.
+    let denom = cw20_denom(&info.sender);
+    ensure_denom_accepted(deps.storage, &denom)?;
+    let payment = MessageInfo {
+        sender: deps.api.addr_validate(&wrapper.sender)?,
+        funds: vec![Coin {
+            denom,
+            amount: wrapper.amount,
         }],
     };
 
-    let restaurant = RESTAURANTS.load(deps.storage, &order.restaurant_id)?;
-    let payment_msg = BankMsg::Send {
-        to_address: restaurant.restaurant_address.to_string(),
-        amount: vec![Coin {
-            denom: NATIVE_DENOM.to_string(),
-            amount: remaining_amount,
-        }],
-    };
+    match from_json(&wrapper.msg)? {
+        ReceiveMsg::CreateOrder {
+            restaurant_id,
+            items,
+            tip,
+            delivery_code_hash,
+        } => create_order(
+            deps,
+            env,
+            payment,
+            restaurant_id,
+            items,
+            tip,
+            delivery_code_hash,
+        ),
+    }
+}
+
+fn next_order_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let seq = ORDER_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    ORDER_SEQ.save(storage, &seq)?;
+    Ok(order_id(seq))
+}
+
+pub fn confirm_delivery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+    delivery_code: Option<String>,
+) -> Result<Response, ContractError> {
+    let order = ORDERS.load(deps.storage, &order_id)?;
+    if !order.status.out_for_delivery() {
+        return Err(ContractError::OrderNotInDelivery {});
+    }
+
+    let rider_id = order
+        .rider_id
+        .as_ref()
+        .ok_or(ContractError::NoRiderAssigned {})?;
+    let rider = RIDERS.load(deps.storage, rider_id)?;
+    if info.sender != rider.wallet {
+        return Err(ContractError::Unauthorized {});
+    }
+    // Orders stored before delivery codes were required carry no hash.
+    if let Some(expected_hash) = &order.delivery_code_hash {
+        let code = delivery_code
+            .filter(|code| code.len() >= MIN_DELIVERY_CODE_LENGTH)
+            .ok_or(ContractError::InvalidDeliveryCode {})?;
+        let code_hash = Sha256::digest(code.as_bytes());
+        if salted_delivery_code_hash(&order.id, &order.customer, &code_hash) != *expected_hash {
+            return Err(ContractError::InvalidDeliveryCode {});
+        }
+    }
+
+    complete_delivery(deps, env, &info.sender, order, rider, "confirm_delivery")
+}
+
+pub fn confirm_receipt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let order = ORDERS.load(deps.storage, &order_id)?;
+    if !order.status.out_for_delivery() {
+        return Err(ContractError::OrderNotInDelivery {});
+    }
+    if info.sender != order.customer {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let rider_id = order
+        .rider_id
+        .as_ref()
+        .ok_or(ContractError::NoRiderAssigned {})?;
+    let rider = RIDERS.load(deps.storage, rider_id)?;
+
+    complete_delivery(deps, env, &info.sender, order, rider, "confirm_receipt")
+}
+
+fn complete_delivery(
+    deps: DepsMut,
+    env: Env,
+    confirmed_by: &Addr,
+    mut order: Order,
+    mut rider: Rider,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let order_id = order.id.clone();
+
+    let escrow = ESCROWS.load(deps.storage, &order_id)?;
+    if escrow.released {
+        return Err(ContractError::FundsAlreadyReleased {});
+    }
+
+    let contract_balance = query_contract_balance(deps.as_ref(), &env, &escrow.denom)?;
+    if contract_balance < escrow.amount {
+        return Err(ContractError::InsufficientEscrowBalance {});
+    }
+
+    order.set_status(OrderStatus::Completed, confirmed_by, env.block.time);
+    order.completed_at = Some(env.block.time);
+    ORDERS.save(deps.storage, &order_id, &order)?;
+    rider.deliveries_completed += 1;
+    RIDERS.save(deps.storage, &rider.id, &rider)?;
+
+    // With a dispute window the escrow stays locked until settle_order is called.
+    let config = PLATFORM_CONFIG.load(deps.storage)?;
+    if config.dispute_window == 0 {
+        settle_escrow(deps.storage, &order, &escrow, &config)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("order_id", order_id)
+        .add_attribute("status", "Completed"))
+}
+
+fn settle_escrow(
+    storage: &mut dyn Storage,
+    order: &Order,
+    escrow: &Escrow,
+    config: &PlatformConfig,
+) -> Result<(), ContractError> {
+    let rider_id = order
+        .rider_id
+        .as_ref()
+        .ok_or(ContractError::NoRiderAssigned {})?;
+    let rider = RIDERS.load(storage, rider_id)?;
+    let restaurant = RESTAURANTS.load(storage, &order.restaurant_id)?;
+
+    let fee_amount = order.total * config.fee_percentage;
+    let rider_amount = order
+        .delivery_fee
+        .checked_add(order.tip)
+        .map_err(|_| ContractError::Overflow {})?;
+    let remaining_amount = escrow
+        .amount
+        .checked_sub(fee_amount)
+        .and_then(|amount| amount.checked_sub(rider_amount))
+        .map_err(|_| ContractError::Overflow {})?;
+
+    credit_claimable(
+        storage,
+        config.fee_address.as_str(),
+        &escrow.denom,
+        fee_amount,
+    )?;
+    credit_claimable(
+        storage,
+        restaurant.payout_address.as_str(),
+        &escrow.denom,
+        remaining_amount,
+    )?;
+    credit_claimable(storage, rider.wallet.as_str(), &escrow.denom, rider_amount)?;
+
+    ESCROWS.save(
+        storage,
+        &order.id,
+        &Escrow {
+            order_id: order.id.clone(),
+            amount: Uint128::zero(),
+            released: true,
+            denom: escrow.denom.clone(),
+        },
+    )?;
+    Ok(())
+}
+
+fn dispute_window_open(order: &Order, config: &PlatformConfig, now: Timestamp) -> bool {
+    order
+        .completed_at
+        .is_some_and(|completed_at| now <= completed_at.plus_seconds(config.dispute_window))
+}
+
+pub fn settle_order(deps: DepsMut, env: Env, order_id: String) -> Result<Response, ContractError> {
+    let order = ORDERS.load(deps.storage, &order_id)?;
+    if order.status != OrderStatus::Completed {
+        return Err(ContractError::OrderNotCompleted {});
+    }
+    let escrow = ESCROWS.load(deps.storage, &order_id)?;
+    if escrow.released {
+        return Err(ContractError::FundsAlreadyReleased {});
+    }
+    let config = PLATFORM_CONFIG.load(deps.storage)?;
+    if dispute_window_open(&order, &config, env.block.time) {
+        return Err(ContractError::DisputeWindowOpen {});
+    }
+
+    settle_escrow(deps.storage, &order, &escrow, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "settle_order")
+        .add_attribute("order_id", order_id))
+}
+
+pub fn open_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+    if order.customer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let escrow = ESCROWS.load(deps.storage, &order_id)?;
+    if escrow.released {
+        return Err(ContractError::FundsAlreadyReleased {});
+    }
+    // Orders can be disputed while out for delivery, or after delivery
+    // until the dispute window closes.
+    let config = PLATFORM_CONFIG.load(deps.storage)?;
+    let disputable = match order.status {
+        OrderStatus::PickedUp | OrderStatus::InDelivery => true,
+        OrderStatus::Completed => dispute_window_open(&order, &config, env.block.time),
+        _ => false,
+    };
+    if !disputable {
+        return Err(ContractError::DisputeWindowClosed {});
+    }
+
+    order.set_status(OrderStatus::Disputed, &info.sender, env.block.time);
+    order.dispute = Some(Dispute {
+        reason,
+        opened_at: env.block.time,
+        resolution: None,
+    });
+    ORDERS.save(deps.storage, &order_id, &order)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "open_dispute")
+        .add_attribute("order_id", order_id))
+}
+
+pub fn resolve_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+    customer_amount: Uint128,
+    restaurant_amount: Uint128,
+    rider_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = PLATFORM_CONFIG.load(deps.storage)?;
+    if !config.owners.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+    if order.status != OrderStatus::Disputed {
+        return Err(ContractError::OrderNotDisputed {});
+    }
+    let escrow = ESCROWS.load(deps.storage, &order_id)?;
+    let split_total = customer_amount
+        .checked_add(restaurant_amount)
+        .and_then(|amount| amount.checked_add(rider_amount))
+        .map_err(|_| ContractError::Overflow {})?;
+    if split_total != escrow.amount {
+        return Err(ContractError::InvalidDisputeSplit {});
+    }
 
-    order.status = OrderStatus::Completed;
+    let restaurant = RESTAURANTS.load(deps.storage, &order.restaurant_id)?;
+    let mut payouts = vec![
+        (order.customer.clone(), customer_amount),
+        (restaurant.payout_address, restaurant_amount),
+    ];
+    if !rider_amount.is_zero() {
+        let rider_id = order
+            .rider_id
+            .as_ref()
+            .ok_or(ContractError::NoRiderAssigned {})?;
+        payouts.push((RIDERS.load(deps.storage, rider_id)?.wallet, rider_amount));
+    }
+    let messages = payouts
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(address, amount)| payout_msg(&address, &escrow.denom, amount))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if let Some(dispute) = order.dispute.as_mut() {
+        dispute.resolution = Some(DisputeResolution {
+            resolved_by: info.sender.clone(),
+            resolved_at: env.block.time,
+            customer_amount,
+            restaurant_amount,
+            rider_amount,
+        });
+    }
+    order.set_status(OrderStatus::Resolved, &info.sender, env.block.time);
     ORDERS.save(deps.storage, &order_id, &order)?;
     ESCROWS.save(
         deps.storage,
@@ -381,19 +1413,174 @@ pub fn confirm_delivery(
             order_id: order_id.clone(),
             amount: Uint128::zero(),
             released: true,
+            denom: escrow.denom.clone(),
         },
     )?;
 
     Ok(Response::new()
-        .add_message(fee_msg)
-        .add_message(payment_msg)
-        .add_attribute("action", "confirm_delivery")
+        .add_messages(messages)
+        .add_attribute("action", "resolve_dispute")
         .add_attribute("order_id", order_id)
-        .add_attribute("status", "Completed"))
+        .add_attribute("customer_amount", customer_amount)
+        .add_attribute("restaurant_amount", restaurant_amount)
+        .add_attribute("rider_amount", rider_amount))
+}
+
+pub fn review_restaurant(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+    rating: u8,
+    comment: String,
+) -> Result<Response, ContractError> {
+    let order = ORDERS.load(deps.storage, &order_id)?;
+    if order.customer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !order.delivered() {
+        return Err(ContractError::OrderNotCompleted {});
+    }
+    if !(1..=5).contains(&rating) {
+        return Err(ContractError::InvalidRating {});
+    }
+    if comment.len() > MAX_REVIEW_LENGTH {
+        return Err(ContractError::ReviewTooLong {});
+    }
+    let key = (order.restaurant_id.as_str(), order_id.as_str());
+    if RESTAURANT_REVIEWS.has(deps.storage, key) {
+        return Err(ContractError::OrderAlreadyReviewed {});
+    }
+
+    RESTAURANT_REVIEWS.save(
+        deps.storage,
+        key,
+        &Review {
+            order_id: order_id.clone(),
+            restaurant_id: order.restaurant_id.clone(),
+            customer: info.sender,
+            rating,
+            comment,
+            created_at: env.block.time,
+        },
+    )?;
+
+    let mut restaurant = RESTAURANTS.load(deps.storage, &order.restaurant_id)?;
+    restaurant.rating_sum += u64::from(rating);
+    restaurant.rating_count += 1;
+    RESTAURANTS.save(deps.storage, &order.restaurant_id, &restaurant)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "review_restaurant")
+        .add_attribute("order_id", order_id)
+        .add_attribute("restaurant_id", order.restaurant_id)
+        .add_attribute("rating", rating.to_string()))
+}
+
+pub fn rate_rider(
+    deps: DepsMut,
+    info: MessageInfo,
+    order_id: String,
+    rating: u8,
+) -> Result<Response, ContractError> {
+    let order = ORDERS.load(deps.storage, &order_id)?;
+    if order.customer != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !order.delivered() {
+        return Err(ContractError::OrderNotCompleted {});
+    }
+    if !(1..=5).contains(&rating) {
+        return Err(ContractError::InvalidRating {});
+    }
+    if RIDER_RATINGS.has(deps.storage, &order_id) {
+        return Err(ContractError::RiderAlreadyRated {});
+    }
+    let rider_id = order.rider_id.ok_or(ContractError::NoRiderAssigned {})?;
+    RIDER_RATINGS.save(deps.storage, &order_id, &rating)?;
+
+    let mut rider = RIDERS.load(deps.storage, &rider_id)?;
+    rider.rating_sum += u64::from(rating);
+    rider.rating_count += 1;
+
+    let threshold = PLATFORM_CONFIG
+        .load(deps.storage)?
+        .rider_suspension_threshold;
+    let suspended = rider.is_registered
+        && rider.rating_count >= MIN_RIDER_RATINGS_FOR_SUSPENSION
+        && rider
+            .average_rating()
+            .is_some_and(|average| average < threshold);
+    if suspended {
+        rider.is_registered = false;
+    }
+    RIDERS.save(deps.storage, &rider_id, &rider)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "rate_rider")
+        .add_attribute("order_id", order_id)
+        .add_attribute("rider_id", rider_id)
+        .add_attribute("rating", rating.to_string())
+        .add_attribute("suspended", suspended.to_string()))
+}
+
+fn credit_claimable(
+    storage: &mut dyn Storage,
+    address: &str,
+    denom: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if amount.is_zero() {
+        return Ok(());
+    }
+    CLAIMABLE_BALANCES.update(storage, (address, denom), |balance| {
+        balance
+            .unwrap_or_default()
+            .checked_add(amount)
+            .map_err(|_| ContractError::Overflow {})
+    })?;
+    Ok(())
+}
+
+pub fn withdraw(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let balances = CLAIMABLE_BALANCES
+        .prefix(info.sender.as_str())
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if balances.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    let mut amount = vec![];
+    let mut cw20_transfers = vec![];
+    for (denom, balance) in balances {
+        CLAIMABLE_BALANCES.remove(deps.storage, (info.sender.as_str(), &denom));
+        if denom.starts_with(CW20_DENOM_PREFIX) {
+            cw20_transfers.push(payout_msg(&info.sender, &denom, balance)?);
+        } else {
+            amount.push(Coin {
+                denom,
+                amount: balance,
+            });
+        }
+    }
+
+    let mut response = Response::new();
+    if !amount.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount,
+        });
+    }
+    Ok(response
+        .add_messages(cw20_transfers)
+        .add_attribute("action", "withdraw")
+        .add_attribute("address", info.sender))
 }
 
 pub fn accept_order(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     order_id: String,
 ) -> Result<Response, ContractError> {
@@ -403,19 +1590,106 @@ pub fn accept_order(
         return Err(ContractError::OrderAlreadyProcessed {});
     }
 
-    let restaurant = RESTAURANTS.load(deps.storage, &order.restaurant_id)?;
-    if info.sender != restaurant.owner {
-        return Err(ContractError::Unauthorized {});
-    }
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &order.restaurant_id,
+        RestaurantRole::Kitchen,
+    )?;
 
-    order.status = OrderStatus::Accepted;
+    order.set_status(OrderStatus::Accepted, &info.sender, env.block.time);
     ORDERS.save(deps.storage, &order_id, &order)?;
+    sync_open_job(deps.storage, &order)?;
 
     Ok(Response::new()
         .add_attribute("action", "accept_order")
         .add_attribute("order_id", order_id))
 }
 
+pub fn start_preparing(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    if order.status != OrderStatus::Accepted {
+        return Err(ContractError::OrderNotAccepted {});
+    }
+
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &order.restaurant_id,
+        RestaurantRole::Kitchen,
+    )?;
+
+    order.set_status(OrderStatus::Preparing, &info.sender, env.block.time);
+    ORDERS.save(deps.storage, &order_id, &order)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_preparing")
+        .add_attribute("order_id", order_id))
+}
+
+pub fn mark_ready_for_pickup(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    if order.status != OrderStatus::Preparing {
+        return Err(ContractError::OrderNotPreparing {});
+    }
+
+    load_restaurant_as(
+        deps.storage,
+        &info.sender,
+        &order.restaurant_id,
+        RestaurantRole::Kitchen,
+    )?;
+
+    order.set_status(OrderStatus::ReadyForPickup, &info.sender, env.block.time);
+    ORDERS.save(deps.storage, &order_id, &order)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "mark_ready_for_pickup")
+        .add_attribute("order_id", order_id))
+}
+
+pub fn pick_up_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    if order.status != OrderStatus::ReadyForPickup {
+        return Err(ContractError::OrderNotReadyForPickup {});
+    }
+
+    let rider_id = order
+        .rider_id
+        .clone()
+        .ok_or(ContractError::NoRiderAssigned {})?;
+    let rider = RIDERS.load(deps.storage, &rider_id)?;
+    if info.sender != rider.wallet {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    order.set_status(OrderStatus::PickedUp, &info.sender, env.block.time);
+    ORDERS.save(deps.storage, &order_id, &order)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pick_up_order")
+        .add_attribute("order_id", order_id)
+        .add_attribute("rider_id", rider_id))
+}
+
 pub fn register_rider(
     deps: DepsMut,
     info: MessageInfo,
@@ -437,6 +1711,10 @@ pub fn register_rider(
         wallet: info.sender.clone(),
         phone_number,
         is_registered: true,
+        deliveries_completed: 0,
+        cancellations: 0,
+        rating_sum: 0,
+        rating_count: 0,
     };
 
     RIDERS.save(deps.storage, &generated_id.clone(), &rider)?;
@@ -448,21 +1726,37 @@ pub fn register_rider(
 
 pub fn assign_rider(
     deps: DepsMut,
+    info: MessageInfo,
     order_id: String,
     rider_id: String,
 ) -> Result<Response, ContractError> {
     let mut order = ORDERS.load(deps.storage, &order_id)?;
 
-    if order.status != OrderStatus::Accepted {
+    if !order.status.awaiting_pickup() {
         return Err(ContractError::OrderNotAccepted {});
     }
 
     let rider = RIDERS.load(deps.storage, &rider_id)?;
-    assert!(rider.is_registered == true, "Rider is not registered");
+    let restaurant = RESTAURANTS.load(deps.storage, &order.restaurant_id)?;
+    if info.sender != restaurant.owner && info.sender != rider.wallet {
+        return Err(ContractError::Unauthorized {});
+    }
+    // Only the restaurant can hand an order over from one rider to another.
+    if info.sender != restaurant.owner
+        && order
+            .rider_id
+            .as_ref()
+            .is_some_and(|assigned| *assigned != rider_id)
+    {
+        return Err(ContractError::RiderAlreadyAssigned {});
+    }
+    if !rider.is_registered {
+        return Err(ContractError::RiderNotRegistered {});
+    }
 
     order.rider_id = Some(rider_id.clone());
-    order.status = OrderStatus::InDelivery;
     ORDERS.save(deps.storage, &order_id, &order)?;
+    OPEN_JOBS.remove(deps.storage, &order_id);
 
     Ok(Response::new()
         .add_attribute("action", "assign_rider")
@@ -470,18 +1764,214 @@ pub fn assign_rider(
         .add_attribute("rider_id", rider_id))
 }
 
-pub fn deposit_funds(
+pub fn claim_delivery(
+    deps: DepsMut,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    if !order.status.awaiting_pickup() || order.rider_id.is_some() {
+        return Err(ContractError::OrderNotAccepted {});
+    }
+
+    let rider_id = format!("rider_{}", info.sender);
+    let rider = RIDERS
+        .may_load(deps.storage, &rider_id)?
+        .ok_or(ContractError::RiderNotRegistered {})?;
+    if !rider.is_registered {
+        return Err(ContractError::RiderNotRegistered {});
+    }
+
+    order.rider_id = Some(rider_id.clone());
+    ORDERS.save(deps.storage, &order_id, &order)?;
+    OPEN_JOBS.remove(deps.storage, &order_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_delivery")
+        .add_attribute("order_id", order_id)
+        .add_attribute("rider_id", rider_id))
+}
+
+pub fn decline_delivery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    // Once the food has been picked up the rider has to finish the delivery.
+    if order.status == OrderStatus::PickedUp {
+        return Err(ContractError::OrderInDelivery {});
+    }
+    if !order.status.awaiting_pickup() && order.status != OrderStatus::InDelivery {
+        return Err(ContractError::OrderNotAccepted {});
+    }
+
+    let rider_id = order
+        .rider_id
+        .clone()
+        .ok_or(ContractError::NoRiderAssigned {})?;
+    let mut rider = RIDERS.load(deps.storage, &rider_id)?;
+    if info.sender != rider.wallet {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    order.rider_id = None;
+    if order.status == OrderStatus::InDelivery {
+        order.set_status(OrderStatus::Accepted, &info.sender, env.block.time);
+    }
+    ORDERS.save(deps.storage, &order_id, &order)?;
+    sync_open_job(deps.storage, &order)?;
+    rider.cancellations += 1;
+    RIDERS.save(deps.storage, &rider_id, &rider)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "decline_delivery")
+        .add_attribute("order_id", order_id)
+        .add_attribute("rider_id", rider_id))
+}
+
+pub fn cancel_order(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     order_id: String,
 ) -> Result<Response, ContractError> {
-    let amount = info.funds[0].amount;
-    let escrow = Escrow {
-        order_id: order_id.clone(),
-        amount,
-        released: false,
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    if order.status != OrderStatus::Created {
+        return Err(ContractError::OrderAlreadyProcessed {});
+    }
+
+    let cancelled_by = if info.sender == order.customer {
+        "customer"
+    } else {
+        load_restaurant_as(
+            deps.storage,
+            &info.sender,
+            &order.restaurant_id,
+            RestaurantRole::Kitchen,
+        )?;
+        "restaurant"
     };
 
-    ESCROWS.save(deps.storage, &order_id, &escrow)?;
-    Ok(Response::new().add_attribute("action", "deposit_funds"))
+    order.set_status(OrderStatus::Cancelled, &info.sender, env.block.time);
+    let refund_msg = refund_escrow(deps, &mut order)?;
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_attribute("action", "cancel_order")
+        .add_attribute("order_id", order_id)
+        .add_attribute("cancelled_by", cancelled_by))
+}
+
+pub fn claim_expired_order(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: String,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, &order_id)?;
+
+    if order.status != OrderStatus::Created {
+        return Err(ContractError::OrderAlreadyProcessed {});
+    }
+
+    let config = PLATFORM_CONFIG.load(deps.storage)?;
+    let expires_at = order
+        .created_at
+        .plus_seconds(config.order_acceptance_timeout);
+    if env.block.time < expires_at {
+        return Err(ContractError::OrderNotExpired {});
+    }
+
+    order.set_status(OrderStatus::Cancelled, &info.sender, env.block.time);
+    let refund_msg = refund_escrow(deps, &mut order)?;
+
+    Ok(Response::new()
+        .add_message(refund_msg)
+        .add_attribute("action", "claim_expired_order")
+        .add_attribute("order_id", order_id))
+}
+
+fn refund_escrow(deps: DepsMut, order: &mut Order) -> Result<CosmosMsg, ContractError> {
+    let escrow = ESCROWS.load(deps.storage, &order.id)?;
+    if escrow.released {
+        return Err(ContractError::FundsAlreadyReleased {});
+    }
+
+    ORDERS.save(deps.storage, &order.id, order)?;
+    restock_order_items(deps.storage, order)?;
+    ESCROWS.save(
+        deps.storage,
+        &order.id,
+        &Escrow {
+            order_id: order.id.clone(),
+            amount: Uint128::zero(),
+            released: true,
+            denom: escrow.denom.clone(),
+        },
+    )?;
+
+    Ok(payout_msg(&order.customer, &escrow.denom, escrow.amount)?)
+}
+
+fn payout_msg(recipient: &Addr, denom: &str, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+        None => BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom.to_string(),
+                amount,
+            }],
+        }
+        .into(),
+    })
+}
+
+fn query_contract_balance(deps: Deps, env: &Env, denom: &str) -> StdResult<Uint128> {
+    match denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => {
+            let response: BalanceResponse = deps.querier.query_wasm_smart(
+                token,
+                &Cw20QueryMsg::Balance {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            Ok(response.balance)
+        }
+        None => Ok(deps
+            .querier
+            .query_balance(&env.contract.address, denom)?
+            .amount),
+    }
+}
+
+fn restock_order_items(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    for item in &order.items {
+        let key = (order.restaurant_id.as_str(), item.item_id.as_str());
+        // Items removed from the menu since the order was placed are skipped.
+        let Some(mut menu_item) = MENU_ITEMS.may_load(storage, key)? else {
+            continue;
+        };
+        if let Some(stock) = menu_item.stock {
+            if stock == 0 {
+                menu_item.available = true;
+            }
+            menu_item.stock = Some(stock.saturating_add(item.quantity));
+            MENU_ITEMS.save(storage, key, &menu_item)?;
+        }
+    }
+    Ok(())
 }
@@ -2,10 +2,54 @@
 use crate::error::ContractError;
 use crate::msg::MigrateMsg;
 
-use crate::state::{OldPlatformConfig, OldRider, PlatformConfig, Rider, PLATFORM_CONFIG, RIDERS};
+use crate::state::sync_open_job;
+use crate::state::{
+    OldPlatformConfig, OldRestaurant, OldRider, Order, PlatformConfig, Restaurant, Rider,
+    DEFAULT_DENOM, DEFAULT_ORDER_ACCEPTANCE_TIMEOUT, ORDERS, PLATFORM_CONFIG, RESTAURANTS, RIDERS,
+};
 
-use cosmwasm_std::{DepsMut, Env, Response, StdResult};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Coin, Decimal, DepsMut, Env, Response, StdResult, Storage};
+use cw_storage_plus::{Bound, Item, Map};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+const DEFAULT_BATCH_LIMIT: u32 = 100;
+const MAX_BATCH_LIMIT: u32 = 500;
+
+// Rewrites over every entry are run in batches so each stays within the gas
+// limit. The cursor for the next batch is only returned while entries remain.
+type Batch<T> = (Vec<(String, T)>, Option<String>);
+
+fn load_batch<T: Serialize + DeserializeOwned>(
+    storage: &dyn Storage,
+    map: &Map<&str, T>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Batch<T>> {
+    let limit = limit.unwrap_or(DEFAULT_BATCH_LIMIT).min(MAX_BATCH_LIMIT) as usize;
+    let mut entries = map
+        .range(
+            storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            cosmwasm_std::Order::Ascending,
+        )
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+    if entries.len() <= limit {
+        return Ok((entries, None));
+    }
+    entries.truncate(limit);
+    let next_start_after = entries.last().map(|(id, _)| id.clone());
+    Ok((entries, next_start_after))
+}
+
+fn with_cursor(response: Response, next_start_after: Option<String>) -> Response {
+    match next_start_after {
+        Some(cursor) => response.add_attribute("next_start_after", cursor),
+        None => response,
+    }
+}
 
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     const OLD_CONFIG: Item<OldPlatformConfig> = Item::new("platform_config");
@@ -16,6 +60,12 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
         owners: vec![old_config.owner_address],
         fee_percentage: old_config.fee_percentage,
         fee_address: old_config.fee_address,
+        order_acceptance_timeout: DEFAULT_ORDER_ACCEPTANCE_TIMEOUT,
+        delivery_fees: vec![Coin::new(0, DEFAULT_DENOM)],
+        rider_suspension_threshold: Decimal::zero(),
+        dispute_window: 0,
+        accepted_denoms: vec![DEFAULT_DENOM.to_string()],
+        accepted_cw20s: vec![],
     };
     PLATFORM_CONFIG.save(deps.storage, &new_config)?;
     Ok(Response::new()
@@ -35,6 +85,10 @@ pub fn update_rider_and_add_user(deps: DepsMut, _env: Env) -> Result<Response, C
             wallet: old_rider.wallet,
             phone_number: String::new(),
             is_registered: old_rider.is_registered,
+            deliveries_completed: 0,
+            cancellations: 0,
+            rating_sum: 0,
+            rating_count: 0,
         };
         RIDERS.save(deps.storage, &id, &new_rider)?;
     }
@@ -43,3 +97,86 @@ pub fn update_rider_and_add_user(deps: DepsMut, _env: Env) -> Result<Response, C
         .add_attribute("action", "update_rider_and_add_user")
         .add_attribute("result", "updated_riders_and_users"))
 }
+
+pub fn build_order_indexes(
+    deps: DepsMut,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    const OLD_ORDERS: Map<&str, Order> = Map::new("orders");
+    let (old_orders, next_start_after) = load_batch(deps.storage, &OLD_ORDERS, start_after, limit)?;
+    let count = old_orders.len();
+    for (id, order) in old_orders {
+        ORDERS.replace(deps.storage, &id, Some(&order), None)?;
+        sync_open_job(deps.storage, &order)?;
+    }
+
+    let response = Response::new()
+        .add_attribute("action", "build_order_indexes")
+        .add_attribute("orders_indexed", count.to_string());
+    Ok(with_cursor(response, next_start_after))
+}
+
+pub fn split_restaurant_addresses(
+    deps: DepsMut,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    const OLD_RESTAURANTS: Map<&str, OldRestaurant> = Map::new("restaurants");
+    let (old_restaurants, next_start_after) =
+        load_batch(deps.storage, &OLD_RESTAURANTS, start_after, limit)?;
+    for (id, old_restaurant) in old_restaurants {
+        // A valid bech32 address was being used as the payout wallet; anything
+        // else was a street address and the owner becomes the payout wallet.
+        let (physical_address, payout_address) =
+            match deps.api.addr_validate(&old_restaurant.restaurant_address) {
+                Ok(addr) => (String::new(), addr),
+                Err(_) => (
+                    old_restaurant.restaurant_address,
+                    old_restaurant.owner.clone(),
+                ),
+            };
+        let new_restaurant = Restaurant {
+            id: old_restaurant.id,
+            owner: old_restaurant.owner,
+            name: old_restaurant.name,
+            image_uri: old_restaurant.image_uri,
+            physical_address,
+            payout_address,
+            opening_hours: vec![],
+            utc_offset_minutes: 0,
+            paused: false,
+            rating_sum: 0,
+            rating_count: 0,
+            denom: DEFAULT_DENOM.to_string(),
+        };
+        RESTAURANTS.replace(deps.storage, &id, Some(&new_restaurant), None)?;
+    }
+
+    let response = Response::new()
+        .add_attribute("action", "split_restaurant_addresses")
+        .add_attribute("result", "split_restaurant_addresses");
+    Ok(with_cursor(response, next_start_after))
+}
+
+pub fn build_restaurant_indexes(
+    deps: DepsMut,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    const OLD_RESTAURANTS: Map<&str, Restaurant> = Map::new("restaurants");
+    let (old_restaurants, next_start_after) =
+        load_batch(deps.storage, &OLD_RESTAURANTS, start_after, limit)?;
+    let count = old_restaurants.len();
+    for (id, restaurant) in old_restaurants {
+        RESTAURANTS.replace(deps.storage, &id, Some(&restaurant), None)?;
+    }
+
+    let response = Response::new()
+        .add_attribute("action", "build_restaurant_indexes")
+        .add_attribute("restaurants_indexed", count.to_string());
+    Ok(with_cursor(response, next_start_after))
+}
@@ -1,14 +1,38 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Coin, Decimal, HexBinary, Uint128};
+use cw20::Cw20ReceiveMsg;
 
-use crate::state::{Escrow, MenuItem, Order, OrderStatus, Restaurant, Rider};
+use crate::state::{
+    Allergen, DietaryInfo, Escrow, MenuCategory, MenuItem, OpeningHours, OptionGroup, Order,
+    OrderStatus, Restaurant, RestaurantRole, Review, Rider,
+};
 
 #[cw_serde]
 #[derive(Eq)]
 pub struct OrderItem {
     pub item_id: String,
     pub quantity: u32,
+    #[serde(default)]
+    pub selected_options: Vec<String>,
+}
+
+#[cw_serde]
+pub struct MenuItemInput {
+    pub item_id: String,
+    pub name: String,
+    pub price: Uint128,
+    pub image_uri: String,
+    #[serde(default)]
+    pub dietary_info: DietaryInfo,
+    #[serde(default)]
+    pub denom: Option<String>,
+}
+
+#[cw_serde]
+pub struct RestockItem {
+    pub item_id: String,
+    pub stock: Option<u32>,
 }
 
 #[cw_serde]
@@ -18,12 +42,41 @@ pub struct InstantiateMsg {
     pub owner_address: String,
     pub fee_percentage: Decimal,
     pub fee_address: String,
+    pub order_acceptance_timeout: Option<u64>,
+    pub delivery_fees: Option<Vec<Coin>>,
+    pub rider_suspension_threshold: Option<Decimal>,
+    pub dispute_window: Option<u64>,
+    pub accepted_denoms: Option<Vec<String>>,
+    pub accepted_cw20s: Option<Vec<String>>,
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    CreateOrder {
+        restaurant_id: String,
+        items: Vec<OrderItem>,
+        tip: Option<Uint128>,
+        /// See ExecuteMsg::CreateOrder.
+        delivery_code_hash: HexBinary,
+    },
 }
 
 #[cw_serde]
 pub enum MigrateMsg {
     ConvertToMultiOwner {},
     UpdateRidersAndUsers {},
+    BuildOrderIndexes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    SplitRestaurantAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    BuildRestaurantIndexes {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -31,42 +84,169 @@ pub enum ExecuteMsg {
     RegisterRestaurant {
         name: String,
         image_uri: String,
-        restaurant_address: String,
+        physical_address: String,
+        payout_address: String,
+        denom: Option<String>,
+    },
+    UpdateRestaurant {
+        restaurant_id: String,
+        name: Option<String>,
+        image_uri: Option<String>,
+        physical_address: Option<String>,
+        payout_address: Option<String>,
+        denom: Option<String>,
+    },
+    SetOpeningHours {
+        restaurant_id: String,
+        opening_hours: Vec<OpeningHours>,
+        utc_offset_minutes: i32,
+    },
+    SetRestaurantPaused {
+        restaurant_id: String,
+        paused: bool,
+    },
+    GrantRestaurantRole {
+        restaurant_id: String,
+        operator: String,
+        role: RestaurantRole,
+    },
+    RevokeRestaurantRole {
+        restaurant_id: String,
+        operator: String,
     },
     AddMenuItem {
+        restaurant_id: String,
         item_id: String,
         name: String,
         price: Uint128,
         image_uri: String,
+        #[serde(default)]
+        dietary_info: DietaryInfo,
+        #[serde(default)]
+        denom: Option<String>,
     },
     RemoveMenuItem {
+        restaurant_id: String,
         item_id: String,
     },
     UpdateMenuItem {
+        restaurant_id: String,
         item_id: String,
         name: Option<String>,
         price: Option<Uint128>,
         available: Option<bool>,
         image_uri: Option<String>,
+        dietary_info: Option<DietaryInfo>,
+        denom: Option<String>,
     },
     ToggleMenuItemAvailability {
+        restaurant_id: String,
+        item_id: String,
+    },
+    SetMenuItemOptionGroups {
+        restaurant_id: String,
         item_id: String,
+        option_groups: Vec<OptionGroup>,
+    },
+    RestockItems {
+        restaurant_id: String,
+        items: Vec<RestockItem>,
+    },
+    UpsertMenuItems {
+        restaurant_id: String,
+        items: Vec<MenuItemInput>,
+    },
+    ReplaceMenu {
+        restaurant_id: String,
+        items: Vec<MenuItemInput>,
+    },
+    AddMenuCategory {
+        restaurant_id: String,
+        category_id: String,
+        name: String,
+    },
+    RemoveMenuCategory {
+        restaurant_id: String,
+        category_id: String,
+    },
+    ReorderMenuCategories {
+        restaurant_id: String,
+        category_ids: Vec<String>,
+    },
+    ReorderMenuItems {
+        restaurant_id: String,
+        category_id: Option<String>,
+        item_ids: Vec<String>,
     },
     CreateOrder {
         restaurant_id: String,
         items: Vec<OrderItem>,
+        tip: Option<Uint128>,
+        /// sha256 of the delivery code the customer hands to the rider, who
+        /// submits it with ConfirmDelivery. The client must generate the code
+        /// as a random secret of at least 32 bytes (MIN_DELIVERY_CODE_LENGTH);
+        /// do not let users pick a PIN. Only the hash is checked here, so an
+        /// order committed to a shorter code can never be confirmed by the
+        /// rider and has to be completed by the customer with ConfirmReceipt.
+        delivery_code_hash: HexBinary,
     },
+    Receive(Cw20ReceiveMsg),
     AcceptOrder {
         order_id: String,
     },
+    StartPreparing {
+        order_id: String,
+    },
+    MarkReadyForPickup {
+        order_id: String,
+    },
+    PickUpOrder {
+        order_id: String,
+    },
     AssignRider {
         order_id: String,
         rider_id: String,
     },
+    ClaimDelivery {
+        order_id: String,
+    },
+    DeclineDelivery {
+        order_id: String,
+    },
     ConfirmDelivery {
         order_id: String,
+        delivery_code: Option<String>,
+    },
+    ConfirmReceipt {
+        order_id: String,
+    },
+    ReviewRestaurant {
+        order_id: String,
+        rating: u8,
+        comment: String,
+    },
+    RateRider {
+        order_id: String,
+        rating: u8,
+    },
+    SettleOrder {
+        order_id: String,
+    },
+    OpenDispute {
+        order_id: String,
+        reason: String,
+    },
+    ResolveDispute {
+        order_id: String,
+        customer_amount: Uint128,
+        restaurant_amount: Uint128,
+        rider_amount: Uint128,
     },
-    DepositFunds {
+    CancelOrder {
+        order_id: String,
+    },
+    Withdraw {},
+    ClaimExpiredOrder {
         order_id: String,
     },
     AddNewOwner {
@@ -75,6 +255,18 @@ pub enum ExecuteMsg {
     RemoveOwner {
         owner: String,
     },
+    UpdatePlatformConfig {
+        platform_name: Option<String>,
+        platform_description: Option<String>,
+        fee_percentage: Option<Decimal>,
+        fee_address: Option<String>,
+        order_acceptance_timeout: Option<u64>,
+        delivery_fees: Option<Vec<Coin>>,
+        rider_suspension_threshold: Option<Decimal>,
+        dispute_window: Option<u64>,
+        accepted_denoms: Option<Vec<String>>,
+        accepted_cw20s: Option<Vec<String>>,
+    },
     RegisterRider {
         name: String,
         phone_number: String,
@@ -89,19 +281,68 @@ pub enum ExecuteMsg {
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(GetRestaurantsResponse)]
-    GetRestaurants {},
+    GetRestaurants {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(GetRestaurantReviewsResponse)]
+    GetRestaurantReviews {
+        restaurant_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(GetRestaurantOperatorsResponse)]
+    GetRestaurantOperators {
+        restaurant_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetMenuItemsResponse)]
-    GetMenuItems { restaurant_id: String },
+    GetMenuItems {
+        restaurant_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(GetMenuResponse)]
+    GetMenu {
+        restaurant_id: String,
+        #[serde(default)]
+        exclude_allergens: Vec<Allergen>,
+    },
+
+    #[returns(GetOrdersResponse)]
+    GetOrdersFromARestaurant {
+        restaurant_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(GetOrdersResponse)]
+    GetRiderOrders {
+        rider_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetOrdersResponse)]
-    GetOrdersFromARestaurant { restaurant_id: String },
+    GetOpenDeliveryJobs {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetOrderResponse)]
     GetOrderDetails { id: String },
 
-    #[returns(GetOrderStatus)]
-    GetAllSuccessfulOrderStatus { is_delivered: bool },
+    #[returns(GetOrdersResponse)]
+    GetAllSuccessfulOrderStatus {
+        is_delivered: bool,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetOrderStatusResponse)]
     GetOrderStatusById { order_id: String },
@@ -112,17 +353,31 @@ pub enum QueryMsg {
     #[returns(PlatformConfigResponse)]
     GetPlatformConfig {},
 
+    #[returns(GetClaimableBalanceResponse)]
+    GetClaimableBalance { address: String },
+
     #[returns(GetRiderResponse)]
     GetRiderById { rider_id: String },
 
+    #[returns(GetRiderStatsResponse)]
+    GetRiderStats { rider_id: String },
+
     #[returns(GetRiderResponse)]
     GetRiderByAddress { riders_address: String },
 
     #[returns(GetUserRestaurantsResponse)]
-    GetUserOwnedRestaurants { owner: String },
+    GetUserOwnedRestaurants {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetUserOrdersResponse)]
-    GetUserOrders { address: String },
+    GetUserOrders {
+        address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     #[returns(GetLatestOrderIdResponse)]
     GetLatestOrderId { address: String },
@@ -131,6 +386,7 @@ pub enum QueryMsg {
     GetOrderCost {
         restaurant_id: String,
         items: Vec<OrderItem>,
+        tip: Option<Uint128>,
     },
 
     #[returns(GetOwnersResponse)]
@@ -149,13 +405,23 @@ pub struct UserResponse {
     pub is_registered: bool,
 }
 
+#[cw_serde]
+pub struct GetClaimableBalanceResponse {
+    pub address: String,
+    pub balances: Vec<Coin>,
+}
+
 #[cw_serde]
 pub struct GetOwnersResponse {
     pub owners: Vec<String>,
 }
 #[cw_serde]
 pub struct GetOrderCostResponse {
+    pub subtotal: Uint128,
+    pub delivery_fee: Uint128,
+    pub tip: Uint128,
     pub total: Uint128,
+    pub denom: String,
 }
 #[cw_serde]
 pub struct GetLatestOrderIdResponse {
@@ -166,14 +432,43 @@ pub struct GetRiderResponse {
     pub rider: Option<Rider>,
 }
 
+#[cw_serde]
+pub struct RestaurantListing {
+    pub restaurant: Restaurant,
+    pub open_now: bool,
+    pub average_rating: Option<Decimal>,
+}
+
 #[cw_serde]
 pub struct GetRestaurantsResponse {
-    pub restaurants: Vec<Restaurant>,
+    pub restaurants: Vec<RestaurantListing>,
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct RestaurantOperator {
+    pub address: String,
+    pub role: RestaurantRole,
+}
+
+#[cw_serde]
+pub struct GetRestaurantReviewsResponse {
+    pub reviews: Vec<Review>,
+    pub average_rating: Option<Decimal>,
+    pub rating_count: u64,
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct GetRestaurantOperatorsResponse {
+    pub operators: Vec<RestaurantOperator>,
+    pub next_start_after: Option<String>,
 }
 
 #[cw_serde]
 pub struct GetUserRestaurantsResponse {
     pub restaurants: Vec<Restaurant>,
+    pub next_start_after: Option<String>,
 }
 
 #[cw_serde]
@@ -194,16 +489,31 @@ pub struct GetEscrowResponse {
 #[cw_serde]
 pub struct GetMenuItemsResponse {
     pub menu_items: Vec<MenuItem>,
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct MenuSection {
+    pub category: MenuCategory,
+    pub items: Vec<MenuItem>,
+}
+
+#[cw_serde]
+pub struct GetMenuResponse {
+    pub sections: Vec<MenuSection>,
+    pub uncategorized: Vec<MenuItem>,
 }
 
 #[cw_serde]
 pub struct GetOrdersResponse {
     pub orders: Vec<Order>,
+    pub next_start_after: Option<String>,
 }
 
 #[cw_serde]
 pub struct GetUserOrdersResponse {
     pub orders: Vec<Order>,
+    pub next_start_after: Option<String>,
 }
 
 #[cw_serde]
@@ -212,6 +522,16 @@ pub struct GetOrderStatusResponse {
     pub status: OrderStatus,
 }
 
+#[cw_serde]
+pub struct GetRiderStatsResponse {
+    pub rider_id: String,
+    pub deliveries_completed: u64,
+    pub cancellations: u64,
+    pub rating_count: u64,
+    pub average_rating: Option<Decimal>,
+    pub is_registered: bool,
+}
+
 #[cw_serde]
 pub struct PlatformConfigResponse {
     pub platform_name: String,
@@ -219,4 +539,10 @@ pub struct PlatformConfigResponse {
     pub owner_address: String,
     pub fee_percentage: Decimal,
     pub fee_address: String,
+    pub order_acceptance_timeout: u64,
+    pub delivery_fees: Vec<Coin>,
+    pub rider_suspension_threshold: Decimal,
+    pub dispute_window: u64,
+    pub accepted_denoms: Vec<String>,
+    pub accepted_cw20s: Vec<String>,
 }
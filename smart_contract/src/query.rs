@@ -1,24 +1,48 @@
 #[cfg(not(feature = "library"))]
-use crate::state::{Order, OrderStatus, Restaurant, ESCROWS, ORDERS, RESTAURANTS};
+use crate::state::{
+    Order, OrderStatus, Restaurant, ESCROWS, LEGACY_ORDER_IDS_START, MAX_MENU_CATEGORIES,
+    MAX_MENU_ITEMS, OPEN_JOBS, ORDERS, RESTAURANTS,
+};
 use crate::{
+    error::ContractError,
     msg::{
-        GetEscrowResponse, GetLatestOrderIdResponse, GetMenuItemsResponse, GetOrderCostResponse,
-        GetOrderResponse, GetOrdersResponse, GetOwnersResponse, GetRestaurantsResponse,
-        GetRiderResponse, GetUserOrdersResponse, GetUserRestaurantsResponse, OrderItem,
-        PlatformConfigResponse, UserResponse,
+        GetClaimableBalanceResponse, GetEscrowResponse, GetLatestOrderIdResponse,
+        GetMenuItemsResponse, GetMenuResponse, GetOrderCostResponse, GetOrderResponse,
+        GetOrdersResponse, GetOwnersResponse, GetRestaurantOperatorsResponse,
+        GetRestaurantReviewsResponse, GetRestaurantsResponse, GetRiderResponse,
+        GetRiderStatsResponse, GetUserOrdersResponse, GetUserRestaurantsResponse, MenuSection,
+        OrderItem, PlatformConfigResponse, RestaurantListing, RestaurantOperator, UserResponse,
+    },
+    state::{
+        Allergen, MenuCategory, MenuItem, Review, CLAIMABLE_BALANCES, MENU_CATEGORIES, MENU_ITEMS,
+        PLATFORM_CONFIG, RESTAURANT_OPERATORS, RESTAURANT_REVIEWS, RIDERS, USERS,
     },
-    state::{MenuItem, MENU_ITEMS, PLATFORM_CONFIG, RIDERS, USERS},
 };
 
-use cosmwasm_std::{Addr, Deps, StdError, StdResult, Uint128};
+use cosmwasm_std::{Addr, Coin, Deps, Env, StdError, StdResult, Uint128};
+use cw_storage_plus::Bound;
 
 use crate::msg::GetOrderStatusResponse;
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+fn page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize
+}
+
+fn next_cursor<T>(page: &[T], limit: usize, key: impl Fn(&T) -> &String) -> Option<String> {
+    if page.len() < limit {
+        return None;
+    }
+    page.last().map(|item| key(item).clone())
+}
+
 pub fn query_platform_config(deps: Deps) -> StdResult<PlatformConfigResponse> {
     let config = PLATFORM_CONFIG.load(deps.storage)?;
     let owner_address = config
         .owners
-        .get(0)
+        .first()
         .map_or("".to_string(), |addr| addr.to_string());
     Ok(PlatformConfigResponse {
         platform_name: config.platform_name,
@@ -26,9 +50,31 @@ pub fn query_platform_config(deps: Deps) -> StdResult<PlatformConfigResponse> {
         owner_address,
         fee_percentage: config.fee_percentage,
         fee_address: config.fee_address.to_string(),
+        order_acceptance_timeout: config.order_acceptance_timeout,
+        delivery_fees: config.delivery_fees,
+        rider_suspension_threshold: config.rider_suspension_threshold,
+        dispute_window: config.dispute_window,
+        accepted_denoms: config.accepted_denoms,
+        accepted_cw20s: config
+            .accepted_cw20s
+            .into_iter()
+            .map(|token| token.to_string())
+            .collect(),
     })
 }
 
+pub fn get_claimable_balance(
+    deps: Deps,
+    address: String,
+) -> StdResult<GetClaimableBalanceResponse> {
+    let balances = CLAIMABLE_BALANCES
+        .prefix(&address)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(GetClaimableBalanceResponse { address, balances })
+}
+
 pub fn get_owners(deps: Deps) -> StdResult<GetOwnersResponse> {
     let config = PLATFORM_CONFIG.load(deps.storage)?;
     let owners = config
@@ -49,53 +95,247 @@ pub fn get_user(deps: Deps, id: String) -> StdResult<UserResponse> {
         is_registered: user.is_registered,
     })
 }
-pub fn get_all_restaurants(deps: Deps) -> StdResult<GetRestaurantsResponse> {
-    let restaurants: Vec<Restaurant> = RESTAURANTS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?
-        .into_iter()
-        .map(|(_, restaurant)| restaurant)
-        .collect();
-    Ok(GetRestaurantsResponse { restaurants })
+pub fn get_all_restaurants(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetRestaurantsResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let restaurants: Vec<RestaurantListing> = RESTAURANTS
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(_, restaurant)| RestaurantListing {
+                open_now: restaurant.is_open(env.block.time),
+                average_rating: restaurant.average_rating(),
+                restaurant,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&restaurants, limit, |listing| &listing.restaurant.id);
+    Ok(GetRestaurantsResponse {
+        restaurants,
+        next_start_after,
+    })
+}
+
+pub fn get_restaurant_operators(
+    deps: Deps,
+    restaurant_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetRestaurantOperatorsResponse> {
+    let limit = page_limit(limit);
+    let start_after = start_after.map(Addr::unchecked);
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let operators: Vec<RestaurantOperator> = RESTAURANT_OPERATORS
+        .prefix(&restaurant_id)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, role)| RestaurantOperator {
+                address: address.to_string(),
+                role,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&operators, limit, |operator| &operator.address);
+    Ok(GetRestaurantOperatorsResponse {
+        operators,
+        next_start_after,
+    })
+}
+
+pub fn get_restaurant_reviews(
+    deps: Deps,
+    restaurant_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetRestaurantReviewsResponse> {
+    let restaurant = RESTAURANTS.load(deps.storage, &restaurant_id)?;
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let reviews: Vec<Review> = RESTAURANT_REVIEWS
+        .prefix(&restaurant_id)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, review)| review))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&reviews, limit, |review| &review.order_id);
+    Ok(GetRestaurantReviewsResponse {
+        reviews,
+        average_rating: restaurant.average_rating(),
+        rating_count: restaurant.rating_count,
+        next_start_after,
+    })
 }
 
 pub fn get_menu_items_for_restaurant(
     deps: Deps,
     restaurant_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
 ) -> StdResult<GetMenuItemsResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
     let menu_items: Vec<MenuItem> = MENU_ITEMS
+        .prefix(&restaurant_id)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, menu_item)| menu_item))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&menu_items, limit, |menu_item| &menu_item.id);
+    Ok(GetMenuItemsResponse {
+        menu_items,
+        next_start_after,
+    })
+}
+
+pub fn get_menu(
+    deps: Deps,
+    restaurant_id: String,
+    exclude_allergens: Vec<Allergen>,
+) -> StdResult<GetMenuResponse> {
+    let mut categories: Vec<MenuCategory> = MENU_CATEGORIES
         .prefix(&restaurant_id)
         .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?
+        .take(MAX_MENU_CATEGORIES)
+        .map(|item| item.map(|(_, category)| category))
+        .collect::<StdResult<Vec<_>>>()?;
+    categories.sort_by_key(|category| category.position);
+
+    let mut menu_items: Vec<MenuItem> = MENU_ITEMS
+        .prefix(&restaurant_id)
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .take(MAX_MENU_ITEMS)
+        .map(|item| item.map(|(_, menu_item)| menu_item))
+        .filter(|item| {
+            item.as_ref().map_or(true, |menu_item| {
+                !menu_item
+                    .dietary_info
+                    .allergens
+                    .iter()
+                    .any(|allergen| exclude_allergens.contains(allergen))
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    menu_items.sort_by_key(|menu_item| menu_item.position);
+
+    let sections = categories
         .into_iter()
-        .map(|(_, menu_item)| menu_item)
+        .map(|category| MenuSection {
+            items: menu_items
+                .iter()
+                .filter(|menu_item| menu_item.category_id.as_ref() == Some(&category.id))
+                .cloned()
+                .collect(),
+            category,
+        })
         .collect();
-    Ok(GetMenuItemsResponse { menu_items })
+    let uncategorized = menu_items
+        .into_iter()
+        .filter(|menu_item| menu_item.category_id.is_none())
+        .collect();
+    Ok(GetMenuResponse {
+        sections,
+        uncategorized,
+    })
 }
 
 pub fn get_orders_for_restaurant(
     deps: Deps,
     restaurant_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
 ) -> StdResult<GetOrdersResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
     let orders: Vec<Order> = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|item| {
-            let (_, order) = item.ok()?;
-            (order.restaurant_id == restaurant_id).then_some(order)
-        })
-        .collect();
-    Ok(GetOrdersResponse { orders })
+        .idx
+        .restaurant
+        .prefix(restaurant_id)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, order)| order))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&orders, limit, |order| &order.id);
+    Ok(GetOrdersResponse {
+        orders,
+        next_start_after,
+    })
 }
 
-pub fn get_order_status(deps: Deps, is_delivered: bool) -> StdResult<Vec<Order>> {
+pub fn get_rider_orders(
+    deps: Deps,
+    rider_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetOrdersResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
     let orders: Vec<Order> = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?
-        .into_iter()
-        .map(|(_, order)| order)
-        .filter(|order| matches!(order.status, OrderStatus::Completed if is_delivered))
-        .collect();
-    Ok(orders)
+        .idx
+        .rider
+        .prefix(rider_id)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, order)| order))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&orders, limit, |order| &order.id);
+    Ok(GetOrdersResponse {
+        orders,
+        next_start_after,
+    })
+}
+
+pub fn get_order_status(
+    deps: Deps,
+    is_delivered: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetOrdersResponse> {
+    if !is_delivered {
+        return Ok(GetOrdersResponse {
+            orders: vec![],
+            next_start_after: None,
+        });
+    }
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let orders: Vec<Order> = ORDERS
+        .idx
+        .status
+        .prefix(OrderStatus::Completed.as_str().to_string())
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, order)| order))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&orders, limit, |order| &order.id);
+    Ok(GetOrdersResponse {
+        orders,
+        next_start_after,
+    })
+}
+
+pub fn get_open_delivery_jobs(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetOrdersResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let orders = OPEN_JOBS
+        .keys(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|id| ORDERS.load(deps.storage, &id?))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&orders, limit, |order| &order.id);
+    Ok(GetOrdersResponse {
+        orders,
+        next_start_after,
+    })
 }
 
 pub fn get_order_status_by_id(deps: Deps, order_id: String) -> StdResult<GetOrderStatusResponse> {
@@ -128,15 +368,39 @@ pub fn get_rider(deps: Deps, rider_id: String) -> StdResult<GetRiderResponse> {
     Ok(GetRiderResponse { rider })
 }
 
-pub fn get_user_restaurants(deps: Deps, owner: Addr) -> StdResult<GetUserRestaurantsResponse> {
+pub fn get_user_restaurants(
+    deps: Deps,
+    owner: Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetUserRestaurantsResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
     let restaurants: Vec<Restaurant> = RESTAURANTS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|item| {
-            let (_, restaurant) = item.ok()?;
-            (restaurant.owner == owner).then_some(restaurant)
-        })
-        .collect();
-    Ok(GetUserRestaurantsResponse { restaurants })
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, restaurant)| restaurant))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&restaurants, limit, |restaurant| &restaurant.id);
+    Ok(GetUserRestaurantsResponse {
+        restaurants,
+        next_start_after,
+    })
+}
+
+pub fn get_rider_stats(deps: Deps, rider_id: String) -> StdResult<GetRiderStatsResponse> {
+    let rider = RIDERS.load(deps.storage, &rider_id)?;
+    Ok(GetRiderStatsResponse {
+        deliveries_completed: rider.deliveries_completed,
+        cancellations: rider.cancellations,
+        rating_count: rider.rating_count,
+        average_rating: rider.average_rating(),
+        is_registered: rider.is_registered,
+        rider_id,
+    })
 }
 
 pub fn get_rider_by_address(deps: Deps, address: Addr) -> StdResult<GetRiderResponse> {
@@ -145,63 +409,130 @@ pub fn get_rider_by_address(deps: Deps, address: Addr) -> StdResult<GetRiderResp
     Ok(GetRiderResponse { rider })
 }
 
-pub fn get_user_orders(deps: Deps, address: Addr) -> StdResult<GetUserOrdersResponse> {
+pub fn get_user_orders(
+    deps: Deps,
+    address: Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<GetUserOrdersResponse> {
+    let limit = page_limit(limit);
+    let start = start_after.as_deref().map(Bound::exclusive);
     let orders: Vec<Order> = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .filter_map(|item| {
-            let (_, order) = item.ok()?;
-            (order.customer == address).then_some(order)
-        })
-        .collect();
-    Ok(GetUserOrdersResponse { orders })
+        .idx
+        .customer
+        .prefix(address)
+        .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, order)| order))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_cursor(&orders, limit, |order| &order.id);
+    Ok(GetUserOrdersResponse {
+        orders,
+        next_start_after,
+    })
 }
 
 pub fn get_order_cost(
     deps: Deps,
+    env: Env,
     restaurant_id: String,
     items: Vec<OrderItem>,
+    tip: Option<Uint128>,
 ) -> StdResult<GetOrderCostResponse> {
     if items.is_empty() {
         return Err(StdError::generic_err("Empty order"));
     }
-    RESTAURANTS
+    let restaurant = RESTAURANTS
         .load(deps.storage, &restaurant_id)
         .map_err(|_| StdError::generic_err("Restaurant not found"))?;
-    let total = items.iter().fold(Ok(Uint128::zero()), |acc, item| {
-        let acc = acc?;
+    if !restaurant.is_open(env.block.time) {
+        return Err(StdError::generic_err(
+            ContractError::RestaurantClosed {}.to_string(),
+        ));
+    }
+    let mut denom: Option<String> = None;
+    let subtotal = items.iter().try_fold(Uint128::zero(), |acc, item| {
         let menu_item = MENU_ITEMS
             .may_load(deps.storage, (&restaurant_id, &item.item_id))?
             .ok_or_else(|| StdError::generic_err("Item not found"))?;
         if !menu_item.available {
             return Err(StdError::generic_err("Item not available"));
         }
+        let item_denom = menu_item.denom(&restaurant);
+        if denom.get_or_insert_with(|| item_denom.to_string()) != item_denom {
+            return Err(StdError::generic_err(
+                "All items in an order must be priced in the same denom",
+            ));
+        }
+        if menu_item.stock.is_some_and(|stock| stock < item.quantity) {
+            return Err(StdError::generic_err("Insufficient stock"));
+        }
         let item_total = menu_item
-            .price
+            .unit_price(&item.selected_options)
+            .map_err(|err| StdError::generic_err(err.to_string()))?
             .checked_mul(Uint128::from(item.quantity))
             .map_err(|_| StdError::generic_err("Overflow in item total"))?;
         acc.checked_add(item_total)
             .map_err(|_| StdError::generic_err("Overflow in total"))
     })?;
-    if total.is_zero() {
+    if subtotal.is_zero() {
         return Err(StdError::generic_err("Invalid order amount"));
     }
-    Ok(GetOrderCostResponse { total })
+    let denom = denom.unwrap_or_default();
+    let delivery_fee = PLATFORM_CONFIG
+        .load(deps.storage)?
+        .delivery_fee(&denom)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let tip = tip.unwrap_or_default();
+    let total = subtotal
+        .checked_add(delivery_fee)
+        .and_then(|total| total.checked_add(tip))
+        .map_err(|_| StdError::generic_err("Overflow in total"))?;
+    Ok(GetOrderCostResponse {
+        subtotal,
+        delivery_fee,
+        tip,
+        total,
+        denom,
+    })
 }
 
 pub fn get_latest_order_id(deps: Deps, address: Addr) -> StdResult<GetLatestOrderIdResponse> {
-    let latest_order = ORDERS
-        .range(deps.storage, None, None, cosmwasm_std::Order::Descending)
-        .filter_map(|item| {
-            let (id, order) = item.unwrap();
-            if order.customer == address {
-                Some(id)
-            } else {
-                None
-            }
-        })
-        .next();
+    let customer_orders = ORDERS.idx.customer.prefix(address);
+    let newest = customer_orders
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive(LEGACY_ORDER_IDS_START)),
+            cosmwasm_std::Order::Descending,
+        )
+        .next()
+        .transpose()?;
+    // Only customers with no sequenced orders fall back to scanning legacy ids.
+    let latest_order = match newest {
+        Some(order_id) => Some(order_id),
+        None => customer_orders
+            .range(
+                deps.storage,
+                Some(Bound::inclusive(LEGACY_ORDER_IDS_START)),
+                None,
+                cosmwasm_std::Order::Ascending,
+            )
+            .collect::<StdResult<Vec<_>>>()?
+            .into_iter()
+            .map(|(_, order)| order)
+            .max_by_key(|order| (order.created_at, order_sequence(&order.id)))
+            .map(|order| order.id),
+    };
 
     Ok(GetLatestOrderIdResponse {
         order_id: latest_order,
     })
 }
+
+fn order_sequence(order_id: &str) -> u64 {
+    order_id
+        .strip_prefix("order_")
+        .and_then(|seq| seq.parse().ok())
+        .unwrap_or_default()
+}
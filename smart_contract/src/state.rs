@@ -1,9 +1,13 @@
+use std::collections::HashSet;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Coin, Decimal, HexBinary, StdResult, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
+use crate::error::ContractError;
 use crate::msg::OrderItem;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -13,10 +17,129 @@ pub struct MenuItem {
     pub price: Uint128,
     pub available: bool,
     pub image_uri: String,
+    #[serde(default)]
+    pub option_groups: Vec<OptionGroup>,
+    #[serde(default)]
+    pub stock: Option<u32>,
+    #[serde(default)]
+    pub category_id: Option<String>,
+    #[serde(default)]
+    pub position: u32,
+    #[serde(default)]
+    pub dietary_info: DietaryInfo,
+    #[serde(default)]
+    pub denom: Option<String>,
+}
+
+impl MenuItem {
+    // Items without their own denom are priced in the restaurant's denom.
+    pub fn denom<'a>(&'a self, restaurant: &'a Restaurant) -> &'a str {
+        self.denom.as_deref().unwrap_or(&restaurant.denom)
+    }
+
+    pub fn unit_price(&self, selected_options: &[String]) -> Result<Uint128, ContractError> {
+        let mut seen = HashSet::new();
+        if !selected_options
+            .iter()
+            .all(|option_id| seen.insert(option_id))
+        {
+            return Err(ContractError::InvalidOptionSelection {});
+        }
+
+        let mut price = self.price;
+        let mut matched = 0;
+        for group in &self.option_groups {
+            let mut selections = 0;
+            for option in &group.options {
+                if selected_options.contains(&option.id) {
+                    selections += 1;
+                    price = price
+                        .checked_add(option.price_delta)
+                        .map_err(|_| ContractError::Overflow {})?;
+                }
+            }
+            if selections < group.min_selections || selections > group.max_selections {
+                return Err(ContractError::InvalidOptionSelection {});
+            }
+            matched += selections as usize;
+        }
+        if matched != selected_options.len() {
+            return Err(ContractError::InvalidOptionSelection {});
+        }
+        Ok(price)
+    }
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub enum Allergen {
+    Gluten,
+    Crustaceans,
+    Eggs,
+    Fish,
+    Peanuts,
+    Soybeans,
+    Dairy,
+    Nuts,
+    Celery,
+    Mustard,
+    Sesame,
+    Sulphites,
+    Lupin,
+    Molluscs,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub enum DietaryFlag {
+    Vegetarian,
+    Vegan,
+    Halal,
+    Kosher,
+    GlutenFree,
+    DairyFree,
+}
+
+#[cw_serde]
+#[derive(Default, Eq)]
+pub struct DietaryInfo {
+    pub allergens: Vec<Allergen>,
+    pub dietary_flags: Vec<DietaryFlag>,
+    pub calories: Option<u32>,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct MenuCategory {
+    pub id: String,
+    pub name: String,
+    pub position: u32,
+}
+
+// GetMenu returns a whole menu in one response, so menus are capped.
+pub const MAX_MENU_ITEMS: usize = 200;
+pub const MAX_MENU_CATEGORIES: usize = 30;
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct OptionGroup {
+    pub id: String,
+    pub name: String,
+    pub min_selections: u32,
+    pub max_selections: u32,
+    pub options: Vec<MenuOption>,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct MenuOption {
+    pub id: String,
+    pub name: String,
+    pub price_delta: Uint128,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
-pub struct Restaurant {
+pub struct OldRestaurant {
     pub id: String,
     pub owner: Addr,
     pub name: String,
@@ -24,6 +147,108 @@ pub struct Restaurant {
     pub restaurant_address: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct Restaurant {
+    pub id: String,
+    pub owner: Addr,
+    pub name: String,
+    pub image_uri: String,
+    pub physical_address: String,
+    pub payout_address: Addr,
+    #[serde(default)]
+    pub opening_hours: Vec<OpeningHours>,
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub rating_sum: u64,
+    #[serde(default)]
+    pub rating_count: u64,
+    #[serde(default = "default_denom")]
+    pub denom: String,
+}
+
+impl Restaurant {
+    pub fn average_rating(&self) -> Option<Decimal> {
+        (self.rating_count > 0).then(|| Decimal::from_ratio(self.rating_sum, self.rating_count))
+    }
+
+    // No opening hours configured means the restaurant is open around the clock.
+    pub fn is_open(&self, now: Timestamp) -> bool {
+        if self.paused {
+            return false;
+        }
+        if self.opening_hours.is_empty() {
+            return true;
+        }
+        let local_seconds = now.seconds() as i64 + i64::from(self.utc_offset_minutes) * 60;
+        let days = local_seconds.div_euclid(SECONDS_PER_DAY);
+        // 1970-01-01 was a Thursday.
+        let weekday = Weekday::from_index((days + 3).rem_euclid(7) as u8);
+        let minute = (local_seconds.rem_euclid(SECONDS_PER_DAY) / 60) as u32;
+        self.opening_hours.iter().any(|hours| {
+            hours.day == weekday && hours.open_minute <= minute && minute < hours.close_minute
+        })
+    }
+}
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+pub const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+#[cw_serde]
+#[derive(Eq)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    fn from_index(index: u8) -> Self {
+        match index {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct OpeningHours {
+    pub day: Weekday,
+    pub open_minute: u32,
+    pub close_minute: u32,
+}
+
+#[cw_serde]
+pub struct Review {
+    pub order_id: String,
+    pub restaurant_id: String,
+    pub customer: Addr,
+    pub rating: u8,
+    pub comment: String,
+    pub created_at: Timestamp,
+}
+
+pub const MAX_REVIEW_LENGTH: usize = 280;
+
+#[cw_serde]
+pub enum RestaurantRole {
+    Manager,
+    Kitchen,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Order {
     pub id: String,
@@ -33,6 +258,86 @@ pub struct Order {
     pub total: Uint128,
     pub status: OrderStatus,
     pub rider_id: Option<String>,
+    #[serde(default)]
+    pub created_at: Timestamp,
+    #[serde(default)]
+    pub delivery_fee: Uint128,
+    #[serde(default)]
+    pub tip: Uint128,
+    #[serde(default)]
+    pub completed_at: Option<Timestamp>,
+    #[serde(default)]
+    pub dispute: Option<Dispute>,
+    #[serde(default)]
+    pub delivery_code_hash: Option<HexBinary>,
+    #[serde(default)]
+    pub status_history: Vec<StatusChange>,
+    #[serde(default = "default_denom")]
+    pub denom: String,
+}
+
+// Delivery code hashes are readable by riders, so codes must be long random
+// secrets rather than PINs that could be brute-forced against the hash.
+pub const MIN_DELIVERY_CODE_LENGTH: usize = 32;
+
+/// The stored delivery code hash: the customer's sha256(code) commitment
+/// salted with the order id and customer address.
+pub fn salted_delivery_code_hash(order_id: &str, customer: &Addr, code_hash: &[u8]) -> HexBinary {
+    let mut hasher = Sha256::new();
+    hasher.update(order_id.as_bytes());
+    hasher.update(customer.as_bytes());
+    hasher.update(code_hash);
+    HexBinary::from(hasher.finalize().to_vec())
+}
+
+impl Order {
+    /// Completed orders, plus resolved disputes opened after delivery.
+    pub fn delivered(&self) -> bool {
+        match self.status {
+            OrderStatus::Completed => true,
+            OrderStatus::Resolved => self.completed_at.is_some(),
+            _ => false,
+        }
+    }
+
+    pub fn open_for_riders(&self) -> bool {
+        self.status.awaiting_pickup() && self.rider_id.is_none()
+    }
+
+    pub fn set_status(&mut self, status: OrderStatus, changed_by: &Addr, changed_at: Timestamp) {
+        self.status = status.clone();
+        self.status_history.push(StatusChange {
+            status,
+            changed_by: changed_by.clone(),
+            changed_at,
+        });
+    }
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct StatusChange {
+    pub status: OrderStatus,
+    pub changed_by: Addr,
+    pub changed_at: Timestamp,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct Dispute {
+    pub reason: String,
+    pub opened_at: Timestamp,
+    pub resolution: Option<DisputeResolution>,
+}
+
+#[cw_serde]
+#[derive(Eq)]
+pub struct DisputeResolution {
+    pub resolved_by: Addr,
+    pub resolved_at: Timestamp,
+    pub customer_amount: Uint128,
+    pub restaurant_amount: Uint128,
+    pub rider_amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -50,8 +355,24 @@ pub struct Rider {
     pub wallet: Addr,
     pub phone_number: String,
     pub is_registered: bool,
+    #[serde(default)]
+    pub deliveries_completed: u64,
+    #[serde(default)]
+    pub cancellations: u64,
+    #[serde(default)]
+    pub rating_sum: u64,
+    #[serde(default)]
+    pub rating_count: u64,
+}
+
+impl Rider {
+    pub fn average_rating(&self) -> Option<Decimal> {
+        (self.rating_count > 0).then(|| Decimal::from_ratio(self.rating_sum, self.rating_count))
+    }
 }
 
+pub const MIN_RIDER_RATINGS_FOR_SUSPENSION: u64 = 5;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct User {
     pub id: String,
@@ -66,15 +387,52 @@ pub struct Escrow {
     pub order_id: String,
     pub amount: Uint128,
     pub released: bool,
+    #[serde(default = "default_denom")]
+    pub denom: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema, Debug)]
 pub enum OrderStatus {
     Created,
     Accepted,
+    Preparing,
+    ReadyForPickup,
+    PickedUp,
+    // Only reached by orders assigned a rider before pickup was tracked.
     InDelivery,
     Completed,
     Cancelled,
+    Disputed,
+    // Final status of a disputed order once the escrow has been split.
+    Resolved,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Created => "created",
+            OrderStatus::Accepted => "accepted",
+            OrderStatus::Preparing => "preparing",
+            OrderStatus::ReadyForPickup => "ready_for_pickup",
+            OrderStatus::PickedUp => "picked_up",
+            OrderStatus::InDelivery => "in_delivery",
+            OrderStatus::Completed => "completed",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Disputed => "disputed",
+            OrderStatus::Resolved => "resolved",
+        }
+    }
+
+    pub fn awaiting_pickup(&self) -> bool {
+        matches!(
+            self,
+            OrderStatus::Accepted | OrderStatus::Preparing | OrderStatus::ReadyForPickup
+        )
+    }
+
+    pub fn out_for_delivery(&self) -> bool {
+        matches!(self, OrderStatus::PickedUp | OrderStatus::InDelivery)
+    }
 }
 
 #[cw_serde]
@@ -93,12 +451,153 @@ pub struct PlatformConfig {
     pub owners: Vec<Addr>,
     pub fee_percentage: Decimal,
     pub fee_address: Addr,
+    #[serde(default = "default_order_acceptance_timeout")]
+    pub order_acceptance_timeout: u64,
+    // A flat fee per payment denom, since amounts in different denoms aren't comparable.
+    #[serde(default = "default_delivery_fees")]
+    pub delivery_fees: Vec<Coin>,
+    #[serde(default)]
+    pub rider_suspension_threshold: Decimal,
+    #[serde(default)]
+    pub dispute_window: u64,
+    #[serde(default = "default_accepted_denoms")]
+    pub accepted_denoms: Vec<String>,
+    #[serde(default)]
+    pub accepted_cw20s: Vec<Addr>,
+}
+
+pub const DEFAULT_ORDER_ACCEPTANCE_TIMEOUT: u64 = 30 * 60;
+
+fn default_order_acceptance_timeout() -> u64 {
+    DEFAULT_ORDER_ACCEPTANCE_TIMEOUT
+}
+
+// Everything stored before multi-denom support was paid in uxion.
+pub const DEFAULT_DENOM: &str = "uxion";
+
+fn default_denom() -> String {
+    DEFAULT_DENOM.to_string()
+}
+
+fn default_accepted_denoms() -> Vec<String> {
+    vec![default_denom()]
+}
+
+fn default_delivery_fees() -> Vec<Coin> {
+    vec![Coin::new(0, DEFAULT_DENOM)]
+}
+
+impl PlatformConfig {
+    pub fn delivery_fee(&self, denom: &str) -> Result<Uint128, ContractError> {
+        self.delivery_fees
+            .iter()
+            .find(|fee| fee.denom == denom)
+            .map(|fee| fee.amount)
+            .ok_or_else(|| ContractError::DeliveryFeeNotConfigured {
+                denom: denom.to_string(),
+            })
+    }
+}
+
+// CW20 tokens share the denom namespace with native coins as "cw20:<contract>".
+pub const CW20_DENOM_PREFIX: &str = "cw20:";
+
+pub fn cw20_denom(token: &Addr) -> String {
+    format!("{}{}", CW20_DENOM_PREFIX, token)
 }
 
 pub const PLATFORM_CONFIG: Item<PlatformConfig> = Item::new("platform_config");
-pub const RESTAURANTS: Map<&str, Restaurant> = Map::new("restaurants");
+pub struct RestaurantIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, Restaurant, &'a str>,
+}
+
+impl IndexList<Restaurant> for RestaurantIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Restaurant>> + '_> {
+        let v: Vec<&dyn Index<Restaurant>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+pub const RESTAURANTS: IndexedMap<&str, Restaurant, RestaurantIndexes> = IndexedMap::new(
+    "restaurants",
+    RestaurantIndexes {
+        owner: MultiIndex::new(
+            |_, restaurant| restaurant.owner.clone(),
+            "restaurants",
+            "restaurants__owner",
+        ),
+    },
+);
+pub const RESTAURANT_SEQ: Item<u64> = Item::new("restaurant_seq");
+pub const RESTAURANT_OPERATORS: Map<(&str, &Addr), RestaurantRole> =
+    Map::new("restaurant_operators");
 pub const MENU_ITEMS: Map<(&str, &str), MenuItem> = Map::new("menu_items");
+pub const MENU_CATEGORIES: Map<(&str, &str), MenuCategory> = Map::new("menu_categories");
 pub const RIDERS: Map<&str, Rider> = Map::new("riders");
-pub const ORDERS: Map<&str, Order> = Map::new("orders");
+pub struct OrderIndexes<'a> {
+    pub customer: MultiIndex<'a, Addr, Order, &'a str>,
+    pub restaurant: MultiIndex<'a, String, Order, &'a str>,
+    pub rider: MultiIndex<'a, String, Order, &'a str>,
+    pub status: MultiIndex<'a, String, Order, &'a str>,
+}
+
+impl IndexList<Order> for OrderIndexes<'_> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Order>> + '_> {
+        let v: Vec<&dyn Index<Order>> =
+            vec![&self.customer, &self.restaurant, &self.rider, &self.status];
+        Box::new(v.into_iter())
+    }
+}
+
+pub const ORDERS: IndexedMap<&str, Order, OrderIndexes> = IndexedMap::new(
+    "orders",
+    OrderIndexes {
+        customer: MultiIndex::new(
+            |_, order| order.customer.clone(),
+            "orders",
+            "orders__customer",
+        ),
+        restaurant: MultiIndex::new(
+            |_, order| order.restaurant_id.clone(),
+            "orders",
+            "orders__restaurant",
+        ),
+        rider: MultiIndex::new(
+            |_, order| order.rider_id.clone().unwrap_or_default(),
+            "orders",
+            "orders__rider",
+        ),
+        status: MultiIndex::new(
+            |_, order| order.status.as_str().to_string(),
+            "orders",
+            "orders__status",
+        ),
+    },
+);
+pub const ORDER_SEQ: Item<u64> = Item::new("order_seq");
+
+// Sequenced ids are zero-padded so they sort in creation order. Ids written
+// before that never start with a zero, so they all sort after this bound.
+pub const LEGACY_ORDER_IDS_START: &str = "order_1";
+
+pub fn order_id(seq: u64) -> String {
+    format!("order_{:020}", seq)
+}
+
+// Orders a rider can still claim, kept in step with every change to an
+// order's status or rider so the job board never scans assigned orders.
+pub const OPEN_JOBS: Map<&str, ()> = Map::new("open_jobs");
+
+pub fn sync_open_job(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    if order.open_for_riders() {
+        OPEN_JOBS.save(storage, &order.id, &())
+    } else {
+        OPEN_JOBS.remove(storage, &order.id);
+        Ok(())
+    }
+}
 pub const ESCROWS: Map<&str, Escrow> = Map::new("escrows");
+pub const RESTAURANT_REVIEWS: Map<(&str, &str), Review> = Map::new("restaurant_reviews");
+pub const RIDER_RATINGS: Map<&str, u8> = Map::new("rider_ratings");
 pub const USERS: Map<&str, User> = Map::new("users");
+pub const CLAIMABLE_BALANCES: Map<(&str, &str), Uint128> = Map::new("claimable_balances");
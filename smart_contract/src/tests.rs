@@ -1,22 +1,39 @@
 #[cfg(not(feature = "library"))]
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
 
-    use crate::contract::{execute, query};
+    use crate::contract::{execute, migrate, query};
+    use crate::error::ContractError;
     use crate::msg::{
-        ExecuteMsg, GetOrdersResponse, GetRestaurantsResponse, GetRiderResponse, OrderItem,
-        QueryMsg,
+        ExecuteMsg, GetLatestOrderIdResponse, GetMenuResponse, GetOrderCostResponse,
+        GetOrderResponse, GetOrdersResponse, GetRestaurantsResponse, GetRiderResponse,
+        GetUserRestaurantsResponse, MenuItemInput, MigrateMsg, OrderItem, QueryMsg, ReceiveMsg,
+        RestockItem,
     };
     use crate::state::{
-        OrderStatus, PlatformConfig, Restaurant, MENU_ITEMS, ORDERS, PLATFORM_CONFIG, RESTAURANTS,
+        Allergen, DietaryFlag, DietaryInfo, MenuItem, MenuOption, OldRestaurant, OpeningHours,
+        OptionGroup, Order, OrderStatus, PlatformConfig, Restaurant, Weekday, CLAIMABLE_BALANCES,
+        ESCROWS, MAX_MENU_ITEMS, MENU_ITEMS, OPEN_JOBS, ORDERS, PLATFORM_CONFIG, RESTAURANTS,
         RIDERS,
     };
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
     use cosmwasm_std::{
-        attr, coins, from_json, Addr, Coin, Decimal, Env, MessageInfo, OwnedDeps, Uint128,
+        attr, coins, from_json, Addr, BankMsg, Coin, CosmosMsg, Decimal, Env, HexBinary,
+        MessageInfo, OwnedDeps, Response, StdError, Uint128,
     };
+    use cosmwasm_std::{to_json_binary, ContractResult, SystemResult, WasmMsg};
+    use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use cw_storage_plus::Map;
+    use sha2::{Digest, Sha256};
+
+    const DELIVERY_CODE: &str = "3f9a1c7e5b2d8046a1e9c3b7d5f20864";
+
+    fn delivery_code_hash() -> HexBinary {
+        HexBinary::from(Sha256::digest(DELIVERY_CODE.as_bytes()).to_vec())
+    }
 
     fn setup_contract() -> (
         OwnedDeps<MockStorage, MockApi, MockQuerier>,
@@ -38,6 +55,12 @@ mod tests {
             owners: vec![info.sender.clone()],
             fee_percentage: Decimal::percent(5),
             fee_address: Addr::unchecked("fee_wallet"),
+            order_acceptance_timeout: 600,
+            delivery_fees: vec![Coin::new(0, "uxion")],
+            rider_suspension_threshold: Decimal::zero(),
+            dispute_window: 0,
+            accepted_denoms: vec!["uxion".to_string()],
+            accepted_cw20s: vec![],
         };
         PLATFORM_CONFIG
             .save(&mut deps.storage, &platform_config)
@@ -45,6 +68,89 @@ mod tests {
         (deps, env, info, restaurant_address)
     }
 
+    fn setup_order(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: &Env,
+        info: &MessageInfo,
+        restaurant_address: &Addr,
+    ) {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::RegisterRestaurant {
+                name: "Test Restaurant".to_string(),
+                image_uri: "https://test.com".to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
+                item_id: "item_1".to_string(),
+                name: "Pizza".to_string(),
+                price: Uint128::from(100u128),
+                image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(200, "uxion")),
+            ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 2,
+                    selected_options: vec![],
+                }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
+            },
+        )
+        .unwrap();
+    }
+
+    fn pick_up_order(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+        env: &Env,
+        info: &MessageInfo,
+        order_id: &str,
+    ) {
+        let order_id = order_id.to_string();
+        for (sender, msg) in [
+            (
+                info.clone(),
+                ExecuteMsg::StartPreparing {
+                    order_id: order_id.clone(),
+                },
+            ),
+            (
+                info.clone(),
+                ExecuteMsg::MarkReadyForPickup {
+                    order_id: order_id.clone(),
+                },
+            ),
+            (
+                mock_info("rider", &[]),
+                ExecuteMsg::PickUpOrder { order_id },
+            ),
+        ] {
+            execute(deps.as_mut(), env.clone(), sender, msg).unwrap();
+        }
+    }
+
     #[test]
     fn test_register_restaurant() {
         let (mut deps, env, info, restaurant_address) = setup_contract();
@@ -56,17 +162,23 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
 
         assert_eq!(res.messages.len(), 0);
-        assert_eq!(res.attributes, vec![attr("action", "register_restaurant")]);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "register_restaurant"),
+                attr("restaurant_id", "restaurant_1")
+            ]
+        );
 
-        let restaurant = RESTAURANTS
-            .load(&deps.storage, "restaurant_creator")
-            .unwrap();
+        let restaurant = RESTAURANTS.load(&deps.storage, "restaurant_1").unwrap();
         assert_eq!(restaurant.name, "Test Restaurant");
         assert_eq!(restaurant.owner, Addr::unchecked("creator"));
     }
@@ -77,7 +189,9 @@ mod tests {
         let msg = ExecuteMsg::RegisterRestaurant {
             name: "Test Restaurant".to_string(),
             image_uri: "https://test.com".to_string(),
-            restaurant_address: restaurant_address.to_string(),
+            physical_address: "1 Test Street".to_string(),
+            payout_address: restaurant_address.to_string(),
+            denom: None,
         };
         execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -86,10 +200,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -98,7 +215,7 @@ mod tests {
         assert_eq!(res.attributes, vec![attr("action", "add_menu_item")]);
 
         let menu_item = MENU_ITEMS
-            .load(&deps.storage, ("restaurant_creator", "item_1"))
+            .load(&deps.storage, ("restaurant_1", "item_1"))
             .unwrap();
         assert_eq!(menu_item.name, "Pizza");
         assert_eq!(menu_item.price, Uint128::from(100u128));
@@ -115,7 +232,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -125,10 +244,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -138,11 +260,14 @@ mod tests {
             env.clone(),
             mock_info("customer", &coins(200, "uxion")),
             ExecuteMsg::CreateOrder {
-                restaurant_id: "restaurant_creator".to_string(),
+                restaurant_id: "restaurant_1".to_string(),
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             },
         )
         .unwrap();
@@ -152,13 +277,15 @@ mod tests {
             res.attributes,
             vec![
                 attr("action", "create_order"),
-                attr("order_id", "order_1"),
-                attr("restaurant_id", "restaurant_creator"),
+                attr("order_id", "order_00000000000000000001"),
+                attr("restaurant_id", "restaurant_1"),
                 attr("total", "200")
             ]
         );
 
-        let order = ORDERS.load(&deps.storage, "order_1").unwrap();
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
         assert_eq!(order.customer, Addr::unchecked("customer"));
         assert_eq!(order.total, Uint128::from(200u128));
     }
@@ -174,7 +301,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -184,10 +313,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -197,11 +329,14 @@ mod tests {
             env.clone(),
             mock_info("customer", &coins(200, "uxion")),
             ExecuteMsg::CreateOrder {
-                restaurant_id: "restaurant_creator".to_string(),
+                restaurant_id: "restaurant_1".to_string(),
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             },
         )
         .unwrap();
@@ -211,7 +346,7 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AcceptOrder {
-                order_id: "order_1".to_string(),
+                order_id: "order_00000000000000000001".to_string(),
             },
         )
         .unwrap();
@@ -219,10 +354,15 @@ mod tests {
         assert_eq!(res.messages.len(), 0);
         assert_eq!(
             res.attributes,
-            vec![attr("action", "accept_order"), attr("order_id", "order_1"),]
+            vec![
+                attr("action", "accept_order"),
+                attr("order_id", "order_00000000000000000001"),
+            ]
         );
 
-        let order = ORDERS.load(&deps.storage, "order_1").unwrap();
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
         assert_eq!(order.status, OrderStatus::Accepted);
     }
     #[test]
@@ -264,7 +404,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -274,10 +416,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -287,11 +432,14 @@ mod tests {
             env.clone(),
             mock_info("customer", &coins(200, "uxion")),
             ExecuteMsg::CreateOrder {
-                restaurant_id: "restaurant_creator".to_string(),
+                restaurant_id: "restaurant_1".to_string(),
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             },
         )
         .unwrap();
@@ -301,7 +449,7 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AcceptOrder {
-                order_id: "order_1".to_string(),
+                order_id: "order_00000000000000000001".to_string(),
             },
         )
         .unwrap();
@@ -322,7 +470,7 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AssignRider {
-                order_id: "order_1".to_string(),
+                order_id: "order_00000000000000000001".to_string(),
                 rider_id: "rider_rider".to_string(),
             },
         )
@@ -333,13 +481,15 @@ mod tests {
             res.attributes,
             vec![
                 attr("action", "assign_rider"),
-                attr("order_id", "order_1"),
+                attr("order_id", "order_00000000000000000001"),
                 attr("rider_id", "rider_rider")
             ]
         );
 
-        let order = ORDERS.load(&deps.storage, "order_1").unwrap();
-        assert_eq!(order.status, OrderStatus::InDelivery);
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Accepted);
         assert_eq!(order.rider_id, Some("rider_rider".to_string()));
     }
     #[test]
@@ -353,7 +503,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -363,10 +515,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -377,11 +532,14 @@ mod tests {
             env.clone(),
             customer_info.clone(),
             ExecuteMsg::CreateOrder {
-                restaurant_id: "restaurant_creator".to_string(),
+                restaurant_id: "restaurant_1".to_string(),
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             },
         )
         .unwrap();
@@ -399,7 +557,7 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AcceptOrder {
-                order_id: "order_1".to_string(),
+                order_id: "order_00000000000000000001".to_string(),
             },
         )
         .unwrap();
@@ -421,34 +579,71 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AssignRider {
-                order_id: "order_1".to_string(),
+                order_id: "order_00000000000000000001".to_string(),
                 rider_id: "rider_rider".to_string(),
             },
         )
         .unwrap();
+        pick_up_order(&mut deps, &env, &info, "order_00000000000000000001");
 
         let res = execute(
             deps.as_mut(),
             env.clone(),
             mock_info("rider", &[]),
             ExecuteMsg::ConfirmDelivery {
-                order_id: "order_1".to_string(),
+                order_id: "order_00000000000000000001".to_string(),
+                delivery_code: Some(DELIVERY_CODE.to_string()),
             },
         )
         .unwrap();
 
-        assert_eq!(res.messages.len(), 2);
+        assert_eq!(res.messages.len(), 0);
         assert_eq!(
             res.attributes,
             vec![
                 attr("action", "confirm_delivery"),
-                attr("order_id", "order_1"),
+                attr("order_id", "order_00000000000000000001"),
                 attr("status", "Completed")
             ]
         );
 
-        let order = ORDERS.load(&deps.storage, "order_1").unwrap();
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
         assert_eq!(order.status, OrderStatus::Completed);
+
+        let fee_balance = CLAIMABLE_BALANCES
+            .load(&deps.storage, ("fee_wallet", "uxion"))
+            .unwrap();
+        assert_eq!(fee_balance, Uint128::new(10));
+        let restaurant_balance = CLAIMABLE_BALANCES
+            .load(&deps.storage, ("res", "uxion"))
+            .unwrap();
+        assert_eq!(restaurant_balance, Uint128::new(190));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("res", &[]),
+            ExecuteMsg::Withdraw {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "res".to_string(),
+                amount: coins(190, "uxion"),
+            })
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("res", &[]),
+            ExecuteMsg::Withdraw {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToWithdraw {});
     }
 
     #[test]
@@ -462,18 +657,28 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
 
-        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetRestaurants {}).unwrap();
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetRestaurants {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
 
         let response: GetRestaurantsResponse = from_json(&res).unwrap();
 
         let restaurants = response.restaurants;
         assert_eq!(restaurants.len(), 1);
-        assert_eq!(restaurants[0].name, "Test Restaurant");
+        assert_eq!(restaurants[0].restaurant.name, "Test Restaurant");
     }
 
     #[test]
@@ -486,21 +691,42 @@ mod tests {
                 owner: Addr::unchecked("owner_1"),
                 name: "Restaurant One".to_string(),
                 image_uri: "https://test.com/restaurant1".to_string(),
-                restaurant_address: Addr::unchecked("q").to_string(),
+                physical_address: "1 Main Street".to_string(),
+                payout_address: Addr::unchecked("payout_1"),
+                opening_hours: vec![],
+                utc_offset_minutes: 0,
+                paused: false,
+                rating_sum: 0,
+                rating_count: 0,
+                denom: "uxion".to_string(),
             },
             Restaurant {
                 id: "restaurant_2".to_string(),
                 owner: Addr::unchecked("owner_2"),
                 name: "Restaurant Two".to_string(),
                 image_uri: "https://test.com/restaurant2".to_string(),
-                restaurant_address: Addr::unchecked("c").to_string(),
+                physical_address: "2 Main Street".to_string(),
+                payout_address: Addr::unchecked("payout_2"),
+                opening_hours: vec![],
+                utc_offset_minutes: 0,
+                paused: false,
+                rating_sum: 0,
+                rating_count: 0,
+                denom: "uxion".to_string(),
             },
             Restaurant {
                 id: "restaurant_3".to_string(),
                 owner: Addr::unchecked("owner_3"),
                 name: "Restaurant Three".to_string(),
                 image_uri: "https://test.com/restaurant3".to_string(),
-                restaurant_address: Addr::unchecked("a").to_string(),
+                physical_address: "3 Main Street".to_string(),
+                payout_address: Addr::unchecked("payout_3"),
+                opening_hours: vec![],
+                utc_offset_minutes: 0,
+                paused: false,
+                rating_sum: 0,
+                rating_count: 0,
+                denom: "uxion".to_string(),
             },
         ];
 
@@ -512,20 +738,30 @@ mod tests {
                 ExecuteMsg::RegisterRestaurant {
                     name: restaurant.name.clone(),
                     image_uri: restaurant.image_uri.clone(),
-                    restaurant_address: Addr::unchecked("q").to_string(),
+                    physical_address: restaurant.physical_address.clone(),
+                    payout_address: restaurant.payout_address.to_string(),
+                    denom: None,
                 },
             )
             .unwrap();
         }
 
-        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetRestaurants {}).unwrap();
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetRestaurants {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
         let response: GetRestaurantsResponse = from_json(&res).unwrap();
         let retrieved_restaurants = response.restaurants;
 
         assert_eq!(retrieved_restaurants.len(), 3);
-        assert_eq!(retrieved_restaurants[0].name, "Restaurant One");
-        assert_eq!(retrieved_restaurants[1].name, "Restaurant Two");
-        assert_eq!(retrieved_restaurants[2].name, "Restaurant Three");
+        assert_eq!(retrieved_restaurants[0].restaurant.name, "Restaurant One");
+        assert_eq!(retrieved_restaurants[1].restaurant.name, "Restaurant Two");
+        assert_eq!(retrieved_restaurants[2].restaurant.name, "Restaurant Three");
     }
 
     #[test]
@@ -534,7 +770,9 @@ mod tests {
         let msg = ExecuteMsg::RegisterRestaurant {
             name: "Test Restaurant".to_string(),
             image_uri: "https://test.com".to_string(),
-            restaurant_address: restaurant_address.to_string(),
+            physical_address: "1 Test Street".to_string(),
+            payout_address: restaurant_address.to_string(),
+            denom: None,
         };
         execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -543,10 +781,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -555,7 +796,7 @@ mod tests {
         assert_eq!(res.attributes, vec![attr("action", "add_menu_item")]);
 
         let menu_item = MENU_ITEMS
-            .load(&deps.storage, ("restaurant_creator", "item_1"))
+            .load(&deps.storage, ("restaurant_1", "item_1"))
             .unwrap();
         assert_eq!(menu_item.name, "Pizza");
         assert_eq!(menu_item.price, Uint128::from(100u128));
@@ -601,24 +842,28 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
 
-        let res: GetRestaurantsResponse = from_json(
+        let res: GetUserRestaurantsResponse = from_json(
             query(
                 deps.as_ref(),
                 env.clone(),
                 QueryMsg::GetUserOwnedRestaurants {
                     owner: Addr::unchecked("creator").to_string(),
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap(),
         )
         .unwrap();
         assert_eq!(res.restaurants.len(), 1);
-        assert_eq!(res.restaurants[0].id, "restaurant_creator");
+        assert_eq!(res.restaurants[0].id, "restaurant_1");
     }
     #[test]
     fn test_get_orders() {
@@ -631,7 +876,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -641,10 +888,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::from(100u128),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -654,11 +904,14 @@ mod tests {
             env.clone(),
             mock_info("customer", &coins(200, "uxion")),
             ExecuteMsg::CreateOrder {
-                restaurant_id: "restaurant_creator".to_string(),
+                restaurant_id: "restaurant_1".to_string(),
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             },
         )
         .unwrap();
@@ -669,13 +922,15 @@ mod tests {
                 env.clone(),
                 QueryMsg::GetUserOrders {
                     address: Addr::unchecked("customer").to_string(),
+                    start_after: None,
+                    limit: None,
                 },
             )
             .unwrap(),
         )
         .unwrap();
         assert_eq!(res.orders.len(), 1);
-        assert_eq!(res.orders[0].id, "order_1");
+        assert_eq!(res.orders[0].id, "order_00000000000000000001");
         assert_eq!(res.orders[0].customer, Addr::unchecked("customer"));
         assert_eq!(res.orders[0].total, Uint128::new(200));
     }
@@ -691,7 +946,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -701,10 +958,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::new(100),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -714,11 +974,14 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::UpdateMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: None,
                 price: Some(Uint128::new(120)),
                 available: Some(false),
                 image_uri: None,
+                dietary_info: None,
+                denom: None,
             },
         )
         .unwrap();
@@ -727,16 +990,16 @@ mod tests {
             res.attributes,
             vec![
                 attr("action", "update_menu_item"),
-                attr("restaurant_id", "restaurant_creator"),
+                attr("restaurant_id", "restaurant_1"),
                 attr("item_id", "item_1"),
             ]
         );
 
         let updated_item = MENU_ITEMS
-            .load(&deps.storage, ("restaurant_creator", "item_1"))
+            .load(&deps.storage, ("restaurant_1", "item_1"))
             .unwrap();
         assert_eq!(updated_item.price, Uint128::new(120));
-        assert_eq!(updated_item.available, false);
+        assert!(!updated_item.available);
     }
 
     #[test]
@@ -750,7 +1013,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -760,10 +1025,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::new(100),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -773,6 +1041,7 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::RemoveMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
             },
         )
@@ -792,7 +1061,9 @@ mod tests {
             ExecuteMsg::RegisterRestaurant {
                 name: "Test Restaurant".to_string(),
                 image_uri: "https://test.com".to_string(),
-                restaurant_address: restaurant_address.to_string(),
+                physical_address: "1 Test Street".to_string(),
+                payout_address: restaurant_address.to_string(),
+                denom: None,
             },
         )
         .unwrap();
@@ -802,10 +1073,13 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
                 name: "Pizza".to_string(),
                 price: Uint128::new(100),
                 image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
             },
         )
         .unwrap();
@@ -815,6 +1089,7 @@ mod tests {
             env.clone(),
             info.clone(),
             ExecuteMsg::ToggleMenuItemAvailability {
+                restaurant_id: "restaurant_1".to_string(),
                 item_id: "item_1".to_string(),
             },
         )
@@ -824,10 +1099,2066 @@ mod tests {
             res.attributes,
             vec![
                 attr("action", "toggle_menu_item_availability"),
-                attr("restaurant_id", "restaurant_creator"),
+                attr("restaurant_id", "restaurant_1"),
                 attr("item_id", "item_1"),
                 attr("available", "false")
             ]
         );
     }
+
+    #[test]
+    fn test_cancel_order_by_customer() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &[]),
+            ExecuteMsg::CancelOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "customer".to_string(),
+                amount: coins(200, "uxion"),
+            })
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "cancel_order"),
+                attr("order_id", "order_00000000000000000001"),
+                attr("cancelled_by", "customer")
+            ]
+        );
+
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        let escrow = ESCROWS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert!(escrow.released);
+        assert_eq!(escrow.amount, Uint128::zero());
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &[]),
+            ExecuteMsg::CancelOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OrderAlreadyProcessed {});
+    }
+
+    #[test]
+    fn test_reject_order_by_restaurant() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("stranger", &[]),
+            ExecuteMsg::CancelOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::CancelOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.attributes[2], attr("cancelled_by", "restaurant"));
+
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_claim_expired_order() {
+        let (mut deps, mut env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::ClaimExpiredOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OrderNotExpired {});
+
+        env.block.time = env.block.time.plus_seconds(600);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::ClaimExpiredOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "customer".to_string(),
+                amount: coins(200, "uxion"),
+            })
+        );
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_order_ids_are_unique_within_a_block() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let mut legacy_order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        legacy_order.id = "order_2".to_string();
+        ORDERS
+            .save(&mut deps.storage, "order_2", &legacy_order)
+            .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(100, "uxion")),
+            ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 1,
+                    selected_options: vec![],
+                }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes[1],
+            attr("order_id", "order_00000000000000000002")
+        );
+
+        let first = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        let second = ORDERS
+            .load(&deps.storage, "order_00000000000000000002")
+            .unwrap();
+        assert_eq!(first.total, Uint128::new(200));
+        assert_eq!(second.total, Uint128::new(100));
+    }
+
+    #[test]
+    fn test_build_order_indexes_migration() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        let legacy_orders: Map<&str, Order> = Map::new("orders");
+        let mut legacy_order = order.clone();
+        legacy_order.id = "order_12345".to_string();
+        legacy_order.status = OrderStatus::Accepted;
+        legacy_orders
+            .save(&mut deps.storage, "order_12345", &legacy_order)
+            .unwrap();
+
+        let res: GetOrdersResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetUserOrders {
+                    address: "customer".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.orders.len(), 1);
+
+        // Run the migration in two batches of one order each.
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg::BuildOrderIndexes {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes[1..],
+            [
+                attr("orders_indexed", "1"),
+                attr("next_start_after", "order_00000000000000000001"),
+            ]
+        );
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg::BuildOrderIndexes {
+                start_after: Some("order_00000000000000000001".to_string()),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1..], [attr("orders_indexed", "1")]);
+
+        let res: GetOrdersResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetOrdersFromARestaurant {
+                    restaurant_id: "restaurant_1".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.orders.len(), 2);
+        assert_eq!(res.orders[1].id, "order_12345");
+        assert!(OPEN_JOBS.has(&deps.storage, "order_12345"));
+        assert!(!OPEN_JOBS.has(&deps.storage, "order_00000000000000000001"));
+
+        // Sequenced ids win over legacy ones, which sort after them as text.
+        let latest: GetLatestOrderIdResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetLatestOrderId {
+                    address: "customer".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            latest.order_id,
+            Some("order_00000000000000000001".to_string())
+        );
+        ORDERS
+            .remove(&mut deps.storage, "order_00000000000000000001")
+            .unwrap();
+        let latest: GetLatestOrderIdResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::GetLatestOrderId {
+                    address: "customer".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(latest.order_id, Some("order_12345".to_string()));
+    }
+
+    #[test]
+    fn test_paginate_user_orders() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        for _ in 0..2 {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("customer", &coins(100, "uxion")),
+                ExecuteMsg::CreateOrder {
+                    restaurant_id: "restaurant_1".to_string(),
+                    items: vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 1,
+                        selected_options: vec![],
+                    }],
+                    tip: None,
+                    delivery_code_hash: delivery_code_hash(),
+                },
+            )
+            .unwrap();
+        }
+
+        let first_page: GetOrdersResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetUserOrders {
+                    address: "customer".to_string(),
+                    start_after: None,
+                    limit: Some(2),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(first_page.orders.len(), 2);
+        assert_eq!(
+            first_page.next_start_after,
+            Some("order_00000000000000000002".to_string())
+        );
+
+        let second_page: GetOrdersResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetUserOrders {
+                    address: "customer".to_string(),
+                    start_after: first_page.next_start_after,
+                    limit: Some(2),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(second_page.orders.len(), 1);
+        assert_eq!(second_page.orders[0].id, "order_00000000000000000003");
+        assert_eq!(second_page.next_start_after, None);
+    }
+
+    #[test]
+    fn test_page_size_is_capped() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        for _ in 0..34 {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("customer", &coins(100, "uxion")),
+                ExecuteMsg::CreateOrder {
+                    restaurant_id: "restaurant_1".to_string(),
+                    items: vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 1,
+                        selected_options: vec![],
+                    }],
+                    tip: None,
+                    delivery_code_hash: delivery_code_hash(),
+                },
+            )
+            .unwrap();
+        }
+
+        // Asking for more than the maximum page still walks every order once.
+        let mut page_sizes = vec![];
+        let mut order_ids = vec![];
+        let mut start_after = None;
+        loop {
+            let page: GetOrdersResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::GetOrdersFromARestaurant {
+                        restaurant_id: "restaurant_1".to_string(),
+                        start_after: start_after.clone(),
+                        limit: Some(100),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            page_sizes.push(page.orders.len());
+            order_ids.extend(page.orders.into_iter().map(|order| order.id));
+            start_after = page.next_start_after;
+            if start_after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(page_sizes, vec![30, 5]);
+        assert_eq!(
+            order_ids,
+            (1..=35)
+                .map(|n| format!("order_{:020}", n))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_assign_rider_authorization_and_decline() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AcceptOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Test Rider".to_string(),
+                phone_number: "1234567890".to_string(),
+            },
+        )
+        .unwrap();
+
+        let assign_msg = ExecuteMsg::AssignRider {
+            order_id: "order_00000000000000000001".to_string(),
+            rider_id: "rider_rider".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("stranger", &[]),
+            assign_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            assign_msg.clone(),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            assign_msg.clone(),
+        )
+        .unwrap();
+
+        // Another rider can't take over the order, but the restaurant can reassign it.
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider_two", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Second Rider".to_string(),
+                phone_number: "0987654321".to_string(),
+            },
+        )
+        .unwrap();
+        let take_over_msg = ExecuteMsg::AssignRider {
+            order_id: "order_00000000000000000001".to_string(),
+            rider_id: "rider_rider_two".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider_two", &[]),
+            take_over_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RiderAlreadyAssigned {});
+
+        execute(deps.as_mut(), env.clone(), info.clone(), take_over_msg).unwrap();
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.rider_id, Some("rider_rider_two".to_string()));
+        execute(deps.as_mut(), env.clone(), info.clone(), assign_msg.clone()).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::DeclineDelivery {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "decline_delivery"));
+
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Accepted);
+        assert_eq!(order.rider_id, None);
+
+        let mut rider = RIDERS.load(&deps.storage, "rider_rider").unwrap();
+        rider.is_registered = false;
+        RIDERS
+            .save(&mut deps.storage, "rider_rider", &rider)
+            .unwrap();
+
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), assign_msg).unwrap_err();
+        assert_eq!(err, ContractError::RiderNotRegistered {});
+    }
+
+    #[test]
+    fn test_update_restaurant_validates_payout_address() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpdateRestaurant {
+                restaurant_id: "restaurant_1".to_string(),
+                name: None,
+                image_uri: None,
+                physical_address: None,
+                payout_address: Some("12 Baker Street".to_string()),
+                denom: None,
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpdateRestaurant {
+                restaurant_id: "restaurant_1".to_string(),
+                name: None,
+                image_uri: None,
+                physical_address: Some("12 Baker Street".to_string()),
+                payout_address: Some("treasury".to_string()),
+                denom: None,
+            },
+        )
+        .unwrap();
+
+        let restaurant = RESTAURANTS.load(&deps.storage, "restaurant_1").unwrap();
+        assert_eq!(restaurant.physical_address, "12 Baker Street");
+        assert_eq!(restaurant.payout_address, Addr::unchecked("treasury"));
+        assert_eq!(restaurant.name, "Test Restaurant");
+    }
+
+    #[test]
+    fn test_split_restaurant_addresses_migration() {
+        let (mut deps, env, _info, _restaurant_address) = setup_contract();
+
+        let legacy_restaurants: Map<&str, OldRestaurant> = Map::new("restaurants");
+        for (id, restaurant_address) in [
+            ("restaurant_wallet", "payoutwallet"),
+            ("restaurant_street", "12 Baker Street"),
+        ] {
+            legacy_restaurants
+                .save(
+                    &mut deps.storage,
+                    id,
+                    &OldRestaurant {
+                        id: id.to_string(),
+                        owner: Addr::unchecked("owner"),
+                        name: "Legacy".to_string(),
+                        image_uri: "https://test.com".to_string(),
+                        restaurant_address: restaurant_address.to_string(),
+                    },
+                )
+                .unwrap();
+        }
+
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg::SplitRestaurantAddresses {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes.last(),
+            Some(&attr("next_start_after", "restaurant_street"))
+        );
+        let street = RESTAURANTS
+            .load(&deps.storage, "restaurant_street")
+            .unwrap();
+        assert_eq!(street.payout_address, Addr::unchecked("owner"));
+        assert!(RESTAURANTS
+            .may_load(&deps.storage, "restaurant_wallet")
+            .is_err());
+
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg::SplitRestaurantAddresses {
+                start_after: Some("restaurant_street".to_string()),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .all(|attribute| attribute.key != "next_start_after"));
+
+        let wallet = RESTAURANTS
+            .load(&deps.storage, "restaurant_wallet")
+            .unwrap();
+        assert_eq!(wallet.payout_address, Addr::unchecked("payoutwallet"));
+        assert_eq!(wallet.physical_address, "");
+
+        let street = RESTAURANTS
+            .load(&deps.storage, "restaurant_street")
+            .unwrap();
+        assert_eq!(street.payout_address, Addr::unchecked("owner"));
+        assert_eq!(street.physical_address, "12 Baker Street");
+    }
+
+    #[test]
+    fn test_build_restaurant_indexes_migration() {
+        let (mut deps, env, _info, restaurant_address) = setup_contract();
+
+        let unindexed_restaurants: Map<&str, Restaurant> = Map::new("restaurants");
+        for id in ["restaurant_1", "restaurant_2"] {
+            unindexed_restaurants
+                .save(
+                    &mut deps.storage,
+                    id,
+                    &Restaurant {
+                        id: id.to_string(),
+                        owner: Addr::unchecked("owner"),
+                        name: "Legacy".to_string(),
+                        image_uri: "https://test.com".to_string(),
+                        physical_address: "1 Test Street".to_string(),
+                        payout_address: restaurant_address.clone(),
+                        opening_hours: vec![],
+                        utc_offset_minutes: 0,
+                        paused: false,
+                        rating_sum: 0,
+                        rating_count: 0,
+                        denom: "uxion".to_string(),
+                    },
+                )
+                .unwrap();
+        }
+        let owned_restaurants = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| {
+            let res: GetUserRestaurantsResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::GetUserOwnedRestaurants {
+                        owner: "owner".to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.restaurants.len()
+        };
+        assert_eq!(owned_restaurants(&deps), 0);
+
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg::BuildRestaurantIndexes {
+                start_after: None,
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes[1..],
+            [
+                attr("restaurants_indexed", "1"),
+                attr("next_start_after", "restaurant_1"),
+            ]
+        );
+        assert_eq!(owned_restaurants(&deps), 1);
+
+        let res = migrate(
+            deps.as_mut(),
+            env.clone(),
+            MigrateMsg::BuildRestaurantIndexes {
+                start_after: Some("restaurant_1".to_string()),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[1..], [attr("restaurants_indexed", "1")]);
+        assert_eq!(owned_restaurants(&deps), 2);
+    }
+
+    #[test]
+    fn test_restaurant_opening_hours_and_pause() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        // mock_env is Wednesday 02:23 UTC.
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::SetOpeningHours {
+                restaurant_id: "restaurant_1".to_string(),
+                opening_hours: vec![OpeningHours {
+                    day: Weekday::Wednesday,
+                    open_minute: 9 * 60,
+                    close_minute: 17 * 60,
+                }],
+                utc_offset_minutes: 0,
+            },
+        )
+        .unwrap();
+
+        let order_msg = ExecuteMsg::CreateOrder {
+            restaurant_id: "restaurant_1".to_string(),
+            items: vec![OrderItem {
+                item_id: "item_1".to_string(),
+                quantity: 1,
+                selected_options: vec![],
+            }],
+            tip: None,
+            delivery_code_hash: delivery_code_hash(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(100, "uxion")),
+            order_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RestaurantClosed {});
+
+        let cost_query = QueryMsg::GetOrderCost {
+            restaurant_id: "restaurant_1".to_string(),
+            items: vec![OrderItem {
+                item_id: "item_1".to_string(),
+                quantity: 1,
+                selected_options: vec![],
+            }],
+            tip: None,
+        };
+        let err = query(deps.as_ref(), env.clone(), cost_query.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(ContractError::RestaurantClosed {}.to_string())
+        );
+
+        let res: GetRestaurantsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetRestaurants {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(!res.restaurants[0].open_now);
+
+        // UTC+8 puts the restaurant at 10:23 local time.
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::SetOpeningHours {
+                restaurant_id: "restaurant_1".to_string(),
+                opening_hours: vec![OpeningHours {
+                    day: Weekday::Wednesday,
+                    open_minute: 9 * 60,
+                    close_minute: 17 * 60,
+                }],
+                utc_offset_minutes: 8 * 60,
+            },
+        )
+        .unwrap();
+        assert!(query(deps.as_ref(), env.clone(), cost_query).is_ok());
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::SetRestaurantPaused {
+                restaurant_id: "restaurant_1".to_string(),
+                paused: true,
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(100, "uxion")),
+            order_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RestaurantClosed {});
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::SetRestaurantPaused {
+                restaurant_id: "restaurant_1".to_string(),
+                paused: false,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(100, "uxion")),
+            order_msg,
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::SetOpeningHours {
+                restaurant_id: "restaurant_1".to_string(),
+                opening_hours: vec![OpeningHours {
+                    day: Weekday::Monday,
+                    open_minute: 17 * 60,
+                    close_minute: 9 * 60,
+                }],
+                utc_offset_minutes: 0,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidOpeningHours {});
+    }
+
+    #[test]
+    fn test_order_with_menu_item_options() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let option = |id: &str, price_delta: u128| MenuOption {
+            id: id.to_string(),
+            name: id.to_string(),
+            price_delta: Uint128::from(price_delta),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::SetMenuItemOptionGroups {
+                restaurant_id: "restaurant_1".to_string(),
+                item_id: "item_1".to_string(),
+                option_groups: vec![
+                    OptionGroup {
+                        id: "size".to_string(),
+                        name: "Size".to_string(),
+                        min_selections: 1,
+                        max_selections: 1,
+                        options: vec![option("small", 0), option("large", 50)],
+                    },
+                    OptionGroup {
+                        id: "extras".to_string(),
+                        name: "Extras".to_string(),
+                        min_selections: 0,
+                        max_selections: 2,
+                        options: vec![option("cheese", 20), option("bacon", 30)],
+                    },
+                ],
+            },
+        )
+        .unwrap();
+
+        let order_item = |selected_options: &[&str]| OrderItem {
+            item_id: "item_1".to_string(),
+            quantity: 2,
+            selected_options: selected_options.iter().map(|id| id.to_string()).collect(),
+        };
+
+        let cost: GetOrderCostResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetOrderCost {
+                    restaurant_id: "restaurant_1".to_string(),
+                    items: vec![order_item(&["large", "cheese"])],
+                    tip: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cost.subtotal, Uint128::from(340u128));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(340, "uxion")),
+            ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![order_item(&["large", "cheese"])],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
+            },
+        )
+        .unwrap();
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000002")
+            .unwrap();
+        assert_eq!(order.total, Uint128::from(340u128));
+
+        for selection in [
+            vec!["cheese"],
+            vec!["small", "large"],
+            vec!["small", "pepperoni"],
+            vec!["small", "cheese", "cheese"],
+        ] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("customer", &coins(200, "uxion")),
+                ExecuteMsg::CreateOrder {
+                    restaurant_id: "restaurant_1".to_string(),
+                    items: vec![order_item(&selection)],
+                    tip: None,
+                    delivery_code_hash: delivery_code_hash(),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::InvalidOptionSelection {});
+        }
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::SetMenuItemOptionGroups {
+                restaurant_id: "restaurant_1".to_string(),
+                item_id: "item_1".to_string(),
+                option_groups: vec![OptionGroup {
+                    id: "size".to_string(),
+                    name: "Size".to_string(),
+                    min_selections: 2,
+                    max_selections: 1,
+                    options: vec![option("small", 0)],
+                }],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidOptionGroups {});
+    }
+
+    #[test]
+    fn test_menu_item_stock_tracking() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::RestockItems {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![RestockItem {
+                    item_id: "item_1".to_string(),
+                    stock: Some(3),
+                }],
+            },
+        )
+        .unwrap();
+
+        let order = |quantity: u32| ExecuteMsg::CreateOrder {
+            restaurant_id: "restaurant_1".to_string(),
+            items: vec![OrderItem {
+                item_id: "item_1".to_string(),
+                quantity,
+                selected_options: vec![],
+            }],
+            tip: None,
+            delivery_code_hash: delivery_code_hash(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(200, "uxion")),
+            order(2),
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(200, "uxion")),
+            order(2),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientStock {
+                item_id: "item_1".to_string()
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(100, "uxion")),
+            order(1),
+        )
+        .unwrap();
+        let menu_item = MENU_ITEMS
+            .load(&deps.storage, ("restaurant_1", "item_1"))
+            .unwrap();
+        assert_eq!(menu_item.stock, Some(0));
+        assert!(!menu_item.available);
+
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("customer", &[]),
+            ExecuteMsg::CancelOrder {
+                order_id: "order_00000000000000000002".to_string(),
+            },
+        )
+        .unwrap();
+        let menu_item = MENU_ITEMS
+            .load(&deps.storage, ("restaurant_1", "item_1"))
+            .unwrap();
+        assert_eq!(menu_item.stock, Some(2));
+        assert!(menu_item.available);
+    }
+
+    #[test]
+    fn test_bulk_menu_management() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let input = |item_id: &str, price: u128| MenuItemInput {
+            item_id: item_id.to_string(),
+            name: "Pizza".to_string(),
+            price: Uint128::from(price),
+            image_uri: "https://test.com/pizza".to_string(),
+            dietary_info: DietaryInfo::default(),
+            denom: None,
+        };
+        let changed_items = |res: &Response| -> Vec<(String, String)> {
+            res.events
+                .iter()
+                .map(|event| (event.ty.clone(), event.attributes[1].value.clone()))
+                .collect()
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpsertMenuItems {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![input("item_2", 150), input("item_2", 160)],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidMenuItem {
+                item_id: "item_2".to_string(),
+                reason: "duplicate id".to_string()
+            }
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpsertMenuItems {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![input("item_2", 150), input("item_3", 0)],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidMenuItem {
+                item_id: "item_3".to_string(),
+                reason: "zero price".to_string()
+            }
+        );
+        assert!(MENU_ITEMS
+            .may_load(&deps.storage, ("restaurant_1", "item_2"))
+            .unwrap()
+            .is_none());
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpsertMenuItems {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![input("item_1", 100), input("item_2", 150)],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            changed_items(&res),
+            vec![("menu_item_upserted".to_string(), "item_2".to_string())]
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::ReplaceMenu {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![input("item_2", 175), input("item_3", 90)],
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            changed_items(&res),
+            vec![
+                ("menu_item_removed".to_string(), "item_1".to_string()),
+                ("menu_item_upserted".to_string(), "item_2".to_string()),
+                ("menu_item_upserted".to_string(), "item_3".to_string()),
+            ]
+        );
+        assert!(MENU_ITEMS
+            .may_load(&deps.storage, ("restaurant_1", "item_1"))
+            .unwrap()
+            .is_none());
+        let item_2 = MENU_ITEMS
+            .load(&deps.storage, ("restaurant_1", "item_2"))
+            .unwrap();
+        assert_eq!(item_2.price, Uint128::from(175u128));
+    }
+
+    #[test]
+    fn test_menu_size_is_capped() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let items = |count: usize| -> Vec<MenuItemInput> {
+            (0..count)
+                .map(|i| MenuItemInput {
+                    item_id: format!("item_{}", i),
+                    name: "Pizza".to_string(),
+                    price: Uint128::from(100u128),
+                    image_uri: "https://test.com/pizza".to_string(),
+                    dietary_info: DietaryInfo::default(),
+                    denom: None,
+                })
+                .collect()
+        };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::ReplaceMenu {
+                restaurant_id: "restaurant_1".to_string(),
+                items: items(MAX_MENU_ITEMS + 1),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::TooManyMenuItems {
+                max: MAX_MENU_ITEMS
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::ReplaceMenu {
+                restaurant_id: "restaurant_1".to_string(),
+                items: items(MAX_MENU_ITEMS),
+            },
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
+                item_id: "one_too_many".to_string(),
+                name: "Pizza".to_string(),
+                price: Uint128::from(100u128),
+                image_uri: "https://test.com/pizza".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::TooManyMenuItems {
+                max: MAX_MENU_ITEMS
+            }
+        );
+    }
+
+    #[test]
+    fn test_menu_categories() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        let items = ["item_2", "item_3", "item_4"]
+            .iter()
+            .map(|item_id| MenuItemInput {
+                item_id: item_id.to_string(),
+                name: item_id.to_string(),
+                price: Uint128::from(100u128),
+                image_uri: "https://test.com".to_string(),
+                dietary_info: DietaryInfo::default(),
+                denom: None,
+            })
+            .collect();
+        let mut msgs = vec![ExecuteMsg::UpsertMenuItems {
+            restaurant_id: "restaurant_1".to_string(),
+            items,
+        }];
+        for (category_id, name) in [("starters", "Starters"), ("mains", "Mains")] {
+            msgs.push(ExecuteMsg::AddMenuCategory {
+                restaurant_id: "restaurant_1".to_string(),
+                category_id: category_id.to_string(),
+                name: name.to_string(),
+            });
+        }
+        msgs.push(ExecuteMsg::ReorderMenuCategories {
+            restaurant_id: "restaurant_1".to_string(),
+            category_ids: vec!["mains".to_string(), "starters".to_string()],
+        });
+        msgs.push(ExecuteMsg::ReorderMenuItems {
+            restaurant_id: "restaurant_1".to_string(),
+            category_id: Some("mains".to_string()),
+            item_ids: vec!["item_3".to_string(), "item_1".to_string()],
+        });
+        msgs.push(ExecuteMsg::ReorderMenuItems {
+            restaurant_id: "restaurant_1".to_string(),
+            category_id: Some("starters".to_string()),
+            item_ids: vec!["item_2".to_string()],
+        });
+        for msg in msgs {
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::ReorderMenuCategories {
+                restaurant_id: "restaurant_1".to_string(),
+                category_ids: vec!["mains".to_string()],
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidMenuOrder {});
+
+        let get_menu = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier>| -> GetMenuResponse {
+            from_json(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::GetMenu {
+                        restaurant_id: "restaurant_1".to_string(),
+                        exclude_allergens: vec![],
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap()
+        };
+        let item_ids = |items: &[MenuItem]| -> Vec<String> {
+            items.iter().map(|item| item.id.clone()).collect()
+        };
+
+        let menu = get_menu(&deps);
+        assert_eq!(menu.sections.len(), 2);
+        assert_eq!(menu.sections[0].category.id, "mains");
+        assert_eq!(item_ids(&menu.sections[0].items), vec!["item_3", "item_1"]);
+        assert_eq!(menu.sections[1].category.id, "starters");
+        assert_eq!(item_ids(&menu.sections[1].items), vec!["item_2"]);
+        assert_eq!(item_ids(&menu.uncategorized), vec!["item_4"]);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::RemoveMenuCategory {
+                restaurant_id: "restaurant_1".to_string(),
+                category_id: "starters".to_string(),
+            },
+        )
+        .unwrap();
+        let menu = get_menu(&deps);
+        assert_eq!(menu.sections.len(), 1);
+        assert_eq!(item_ids(&menu.uncategorized), vec!["item_2", "item_4"]);
+    }
+
+    #[test]
+    fn test_menu_filtered_by_allergen() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AddMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
+                item_id: "item_2".to_string(),
+                name: "Satay".to_string(),
+                price: Uint128::from(80u128),
+                image_uri: "https://test.com/satay".to_string(),
+                dietary_info: DietaryInfo {
+                    allergens: vec![Allergen::Peanuts],
+                    dietary_flags: vec![DietaryFlag::Vegan, DietaryFlag::Halal],
+                    calories: Some(450),
+                },
+                denom: None,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::UpdateMenuItem {
+                restaurant_id: "restaurant_1".to_string(),
+                item_id: "item_1".to_string(),
+                name: None,
+                price: None,
+                available: None,
+                image_uri: None,
+                dietary_info: Some(DietaryInfo {
+                    allergens: vec![Allergen::Gluten, Allergen::Dairy],
+                    dietary_flags: vec![DietaryFlag::Vegetarian],
+                    calories: None,
+                }),
+                denom: None,
+            },
+        )
+        .unwrap();
+
+        let menu_item_ids = |exclude_allergens: Vec<Allergen>| -> Vec<String> {
+            let menu: GetMenuResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::GetMenu {
+                        restaurant_id: "restaurant_1".to_string(),
+                        exclude_allergens,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            menu.uncategorized.into_iter().map(|item| item.id).collect()
+        };
+
+        assert_eq!(menu_item_ids(vec![]), vec!["item_1", "item_2"]);
+        assert_eq!(menu_item_ids(vec![Allergen::Dairy]), vec!["item_2"]);
+        assert_eq!(menu_item_ids(vec![Allergen::Peanuts]), vec!["item_1"]);
+        assert!(menu_item_ids(vec![Allergen::Gluten, Allergen::Peanuts]).is_empty());
+
+        let satay = MENU_ITEMS
+            .load(&deps.storage, ("restaurant_1", "item_2"))
+            .unwrap();
+        assert_eq!(satay.dietary_info.calories, Some(450));
+    }
+
+    #[test]
+    fn test_delivery_code_confirmation() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin {
+                denom: "uxion".to_string(),
+                amount: Uint128::new(400),
+            }],
+        );
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(200, "uxion")),
+            ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 2,
+                    selected_options: vec![],
+                }],
+                tip: None,
+                delivery_code_hash: HexBinary::from(b"too short".to_vec()),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidDeliveryCodeHash {});
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(200, "uxion")),
+            ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 2,
+                    selected_options: vec![],
+                }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
+            },
+        )
+        .unwrap();
+        // The stored hash is salted, so it differs between orders with the same code.
+        let first = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        let second = ORDERS
+            .load(&deps.storage, "order_00000000000000000002")
+            .unwrap();
+        assert_ne!(first.delivery_code_hash, second.delivery_code_hash);
+        assert_ne!(second.delivery_code_hash, Some(delivery_code_hash()));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Test Rider".to_string(),
+                phone_number: "1234567890".to_string(),
+            },
+        )
+        .unwrap();
+        for order_id in ["order_00000000000000000001", "order_00000000000000000002"] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                info.clone(),
+                ExecuteMsg::AcceptOrder {
+                    order_id: order_id.to_string(),
+                },
+            )
+            .unwrap();
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("rider", &[]),
+                ExecuteMsg::ClaimDelivery {
+                    order_id: order_id.to_string(),
+                },
+            )
+            .unwrap();
+            pick_up_order(&mut deps, &env, &info, order_id);
+        }
+
+        for delivery_code in [None, Some("1234".to_string()), Some("0".repeat(32))] {
+            let err = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("rider", &[]),
+                ExecuteMsg::ConfirmDelivery {
+                    order_id: "order_00000000000000000002".to_string(),
+                    delivery_code,
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::InvalidDeliveryCode {});
+        }
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ConfirmDelivery {
+                order_id: "order_00000000000000000002".to_string(),
+                delivery_code: Some(DELIVERY_CODE.to_string()),
+            },
+        )
+        .unwrap();
+
+        // The customer can confirm receipt without involving the rider.
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ConfirmReceipt {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("customer", &[]),
+            ExecuteMsg::ConfirmReceipt {
+                order_id: "order_00000000000000000001".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "confirm_receipt"));
+        let order = ORDERS
+            .load(&deps.storage, "order_00000000000000000001")
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Completed);
+        assert!(
+            ESCROWS
+                .load(&deps.storage, "order_00000000000000000001")
+                .unwrap()
+                .released
+        );
+    }
+
+    #[test]
+    fn test_short_delivery_code_needs_customer_receipt() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin {
+                denom: "uxion".to_string(),
+                amount: Uint128::new(400),
+            }],
+        );
+
+        // A customer who commits to a short PIN still gets an order...
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &coins(200, "uxion")),
+            ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 2,
+                    selected_options: vec![],
+                }],
+                tip: None,
+                delivery_code_hash: HexBinary::from(Sha256::digest(b"4821").to_vec()),
+            },
+        )
+        .unwrap();
+        let order_id = "order_00000000000000000002";
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Test Rider".to_string(),
+                phone_number: "1234567890".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AcceptOrder {
+                order_id: order_id.to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ClaimDelivery {
+                order_id: order_id.to_string(),
+            },
+        )
+        .unwrap();
+        pick_up_order(&mut deps, &env, &info, order_id);
+
+        // ...but the rider can never confirm it, even with the matching PIN.
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ConfirmDelivery {
+                order_id: order_id.to_string(),
+                delivery_code: Some("4821".to_string()),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidDeliveryCode {});
+
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("customer", &[]),
+            ExecuteMsg::ConfirmReceipt {
+                order_id: order_id.to_string(),
+            },
+        )
+        .unwrap();
+        let order = ORDERS.load(&deps.storage, order_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Completed);
+    }
+
+    #[test]
+    fn test_order_timeline() {
+        let (mut deps, mut env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+        deps.querier.update_balance(
+            env.contract.address.clone(),
+            vec![Coin {
+                denom: "uxion".to_string(),
+                amount: Uint128::new(200),
+            }],
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Test Rider".to_string(),
+                phone_number: "1234567890".to_string(),
+            },
+        )
+        .unwrap();
+        let order_id = "order_00000000000000000001".to_string();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::MarkReadyForPickup {
+                order_id: order_id.clone(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OrderNotPreparing {});
+
+        let steps = [
+            (
+                info.clone(),
+                ExecuteMsg::AcceptOrder {
+                    order_id: order_id.clone(),
+                },
+            ),
+            (
+                info.clone(),
+                ExecuteMsg::StartPreparing {
+                    order_id: order_id.clone(),
+                },
+            ),
+            (
+                info.clone(),
+                ExecuteMsg::MarkReadyForPickup {
+                    order_id: order_id.clone(),
+                },
+            ),
+        ];
+        for (sender, msg) in steps {
+            env.block.time = env.block.time.plus_seconds(60);
+            execute(deps.as_mut(), env.clone(), sender, msg).unwrap();
+        }
+
+        // Riders can still take jobs that are waiting in the kitchen.
+        let jobs: GetOrdersResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetOpenDeliveryJobs {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(jobs.orders.len(), 1);
+        env.block.time = env.block.time.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ClaimDelivery {
+                order_id: order_id.clone(),
+            },
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::PickUpOrder {
+                order_id: order_id.clone(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        env.block.time = env.block.time.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::PickUpOrder {
+                order_id: order_id.clone(),
+            },
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::DeclineDelivery {
+                order_id: order_id.clone(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OrderInDelivery {});
+
+        env.block.time = env.block.time.plus_seconds(60);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ConfirmDelivery {
+                order_id: order_id.clone(),
+                delivery_code: Some(DELIVERY_CODE.to_string()),
+            },
+        )
+        .unwrap();
+
+        let details: GetOrderResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetOrderDetails { id: order_id },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let start = mock_env().block.time;
+        let history: Vec<(OrderStatus, String, u64)> = details
+            .order
+            .status_history
+            .into_iter()
+            .map(|change| {
+                (
+                    change.status,
+                    change.changed_by.to_string(),
+                    change.changed_at.seconds() - start.seconds(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (OrderStatus::Created, "customer".to_string(), 0),
+                (OrderStatus::Accepted, info.sender.to_string(), 60),
+                (OrderStatus::Preparing, info.sender.to_string(), 120),
+                (OrderStatus::ReadyForPickup, info.sender.to_string(), 180),
+                (OrderStatus::PickedUp, "rider".to_string(), 300),
+                (OrderStatus::Completed, "rider".to_string(), 360),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cw20_payments() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpdatePlatformConfig {
+                platform_name: None,
+                platform_description: None,
+                fee_percentage: None,
+                fee_address: None,
+                order_acceptance_timeout: None,
+                delivery_fees: Some(vec![Coin::new(0, "uxion"), Coin::new(0, "cw20:usdc_token")]),
+                rider_suspension_threshold: None,
+                dispute_window: None,
+                accepted_denoms: None,
+                accepted_cw20s: Some(vec!["usdc_token".to_string()]),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpdateRestaurant {
+                restaurant_id: "restaurant_1".to_string(),
+                name: None,
+                image_uri: None,
+                physical_address: None,
+                payout_address: None,
+                denom: Some("cw20:usdc_token".to_string()),
+            },
+        )
+        .unwrap();
+
+        let receive = |amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: "customer".to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&ReceiveMsg::CreateOrder {
+                    restaurant_id: "restaurant_1".to_string(),
+                    items: vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 2,
+                        selected_options: vec![],
+                    }],
+                    tip: None,
+                    delivery_code_hash: delivery_code_hash(),
+                })
+                .unwrap(),
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other_token", &[]),
+            receive(200),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DenomNotAccepted {
+                denom: "cw20:other_token".to_string()
+            }
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("usdc_token", &[]),
+            receive(150),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::IncorrectPayment {});
+
+        let cw20_transfer = |recipient: &str, amount: u128| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "usdc_token".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        };
+
+        // Cancelled CW20 orders are refunded with a token transfer.
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("usdc_token", &[]),
+            receive(200),
+        )
+        .unwrap();
+        let escrow = ESCROWS
+            .load(&deps.storage, "order_00000000000000000002")
+            .unwrap();
+        assert_eq!(escrow.denom, "cw20:usdc_token");
+        assert_eq!(escrow.amount, Uint128::new(200));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &[]),
+            ExecuteMsg::CancelOrder {
+                order_id: "order_00000000000000000002".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, cw20_transfer("customer", 200));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("usdc_token", &[]),
+            receive(200),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Test Rider".to_string(),
+                phone_number: "1234567890".to_string(),
+            },
+        )
+        .unwrap();
+        for msg in [
+            ExecuteMsg::AcceptOrder {
+                order_id: "order_00000000000000000003".to_string(),
+            },
+            ExecuteMsg::AssignRider {
+                order_id: "order_00000000000000000003".to_string(),
+                rider_id: "rider_rider".to_string(),
+            },
+        ] {
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        pick_up_order(&mut deps, &env, &info, "order_00000000000000000003");
+        deps.querier.update_wasm(|_| {
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&BalanceResponse {
+                    balance: Uint128::new(200),
+                })
+                .unwrap(),
+            ))
+        });
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ConfirmDelivery {
+                order_id: "order_00000000000000000003".to_string(),
+                delivery_code: Some(DELIVERY_CODE.to_string()),
+            },
+        )
+        .unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(restaurant_address.as_str(), &[]),
+            ExecuteMsg::Withdraw {},
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.messages[0].msg,
+            cw20_transfer(restaurant_address.as_str(), 190)
+        );
+    }
+
+    #[test]
+    fn test_resolved_dispute_is_not_a_delivery() {
+        let (mut deps, env, info, restaurant_address) = setup_contract();
+        setup_order(&mut deps, &env, &info, &restaurant_address);
+        let order_id = "order_00000000000000000001";
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::RegisterRider {
+                name: "Test Rider".to_string(),
+                phone_number: "1234567890".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AcceptOrder {
+                order_id: order_id.to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("rider", &[]),
+            ExecuteMsg::ClaimDelivery {
+                order_id: order_id.to_string(),
+            },
+        )
+        .unwrap();
+        pick_up_order(&mut deps, &env, &info, order_id);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &[]),
+            ExecuteMsg::OpenDispute {
+                order_id: order_id.to_string(),
+                reason: "Food never arrived".to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::ResolveDispute {
+                order_id: order_id.to_string(),
+                customer_amount: Uint128::new(200),
+                restaurant_amount: Uint128::zero(),
+                rider_amount: Uint128::zero(),
+            },
+        )
+        .unwrap();
+
+        let order = ORDERS.load(&deps.storage, order_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Resolved);
+        assert_eq!(order.completed_at, None);
+        let resolution = order.dispute.unwrap().resolution.unwrap();
+        assert_eq!(resolution.customer_amount, Uint128::new(200));
+
+        let delivered: GetOrdersResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::GetAllSuccessfulOrderStatus {
+                    is_delivered: true,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(delivered.orders.is_empty());
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("customer", &[]),
+            ExecuteMsg::ReviewRestaurant {
+                order_id: order_id.to_string(),
+                rating: 1,
+                comment: "Never arrived".to_string(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OrderNotCompleted {});
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info("customer", &[]),
+            ExecuteMsg::RateRider {
+                order_id: order_id.to_string(),
+                rating: 1,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OrderNotCompleted {});
+    }
 }
@@ -1,14 +1,16 @@
 #[cfg(test)]
 mod tests {
     use tastella::contract::{execute, instantiate, query};
+    use tastella::error::ContractError;
     use tastella::msg::{
         ExecuteMsg, GetEscrowResponse, GetRestaurantsResponse, InstantiateMsg, OrderItem, QueryMsg,
     };
 
-    use cosmwasm_std::{Addr, Coin, Decimal, Empty, Uint128};
+    use cosmwasm_std::{Addr, Coin, Decimal, Empty, HexBinary, Uint128};
     use cw_multi_test::{App, AppBuilder, AppResponse, Contract, ContractWrapper, Executor};
     use lazy_static::lazy_static;
-    use tastella::state::OrderStatus;
+    use sha2::{Digest, Sha256};
+    use tastella::state::{DietaryInfo, OrderStatus, RestaurantRole};
 
     pub fn contract_template() -> Box<dyn Contract<Empty>> {
         let contract = ContractWrapper::new(execute, instantiate, query);
@@ -18,6 +20,8 @@ mod tests {
     const USER: &str = "xion1useraddress";
     const USER_2: &str = "xion1adminaddress";
     const NATIVE_DENOM: &str = "uxion";
+    const USDC_DENOM: &str = "ibc/usdc";
+    const DELIVERY_CODE: &str = "3f9a1c7e5b2d8046a1e9c3b7d5f20864";
 
     lazy_static! {
         static ref RESTAURANT_1: Addr = Addr::unchecked("xion1restaurant1");
@@ -25,6 +29,10 @@ mod tests {
         static ref FEE_WALLET: Addr = Addr::unchecked("xion1fee_wallet");
     }
 
+    fn delivery_code_hash() -> HexBinary {
+        HexBinary::from(Sha256::digest(DELIVERY_CODE.as_bytes()).to_vec())
+    }
+
     fn mock_app() -> App {
         AppBuilder::new().build(|router, _, storage| {
             router
@@ -32,10 +40,16 @@ mod tests {
                 .init_balance(
                     storage,
                     &Addr::unchecked(USER),
-                    vec![Coin {
-                        denom: NATIVE_DENOM.to_string(),
-                        amount: Uint128::new(100000),
-                    }],
+                    vec![
+                        Coin {
+                            denom: NATIVE_DENOM.to_string(),
+                            amount: Uint128::new(100000),
+                        },
+                        Coin {
+                            denom: USDC_DENOM.to_string(),
+                            amount: Uint128::new(100000),
+                        },
+                    ],
                 )
                 .unwrap();
         })
@@ -51,6 +65,12 @@ mod tests {
             owner_address: "xion1adminaddress".to_string(),
             fee_percentage: Decimal::percent(5),
             fee_address: FEE_WALLET.to_string(),
+            order_acceptance_timeout: None,
+            delivery_fees: None,
+            rider_suspension_threshold: None,
+            dispute_window: None,
+            accepted_denoms: None,
+            accepted_cw20s: None,
         };
 
         let contract_addr = app
@@ -73,12 +93,14 @@ mod tests {
         user: &str,
         name: &str,
         image_uri: &str,
-        restaurant_address: Addr,
+        payout_address: Addr,
     ) {
         let register_msg = ExecuteMsg::RegisterRestaurant {
             name: name.to_string(),
             image_uri: image_uri.to_string(),
-            restaurant_address: restaurant_address.to_string(),
+            physical_address: "1 Test Street".to_string(),
+            payout_address: payout_address.to_string(),
+            denom: None,
         };
         app.execute_contract(
             Addr::unchecked(user),
@@ -103,21 +125,25 @@ mod tests {
         .unwrap();
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn add_menu_item(
         app: &mut App,
         contract_addr: &Addr,
         user: &str,
-        _restaurant_id: &str,
+        restaurant_id: &str,
         item_id: &str,
         name: &str,
         price: Uint128,
         image_uri: &str,
     ) {
         let add_menu_item_msg = ExecuteMsg::AddMenuItem {
+            restaurant_id: restaurant_id.to_string(),
             item_id: item_id.to_string(),
             name: name.to_string(),
             price,
             image_uri: image_uri.to_string(),
+            dietary_info: DietaryInfo::default(),
+            denom: None,
         };
         app.execute_contract(
             Addr::unchecked(user),
@@ -139,6 +165,8 @@ mod tests {
         let create_order_msg = ExecuteMsg::CreateOrder {
             restaurant_id: restaurant_id.to_string(),
             items,
+            tip: None,
+            delivery_code_hash: delivery_code_hash(),
         };
         app.execute_contract(
             Addr::unchecked(user),
@@ -149,11 +177,89 @@ mod tests {
         .map_err(|e| anyhow::anyhow!(e))
     }
 
+    fn pick_up_order(
+        app: &mut App,
+        contract_addr: &Addr,
+        restaurant_user: &str,
+        rider: &str,
+        order_id: &str,
+    ) {
+        let order_id = order_id.to_string();
+        for (sender, msg) in [
+            (
+                restaurant_user,
+                ExecuteMsg::StartPreparing {
+                    order_id: order_id.clone(),
+                },
+            ),
+            (
+                restaurant_user,
+                ExecuteMsg::MarkReadyForPickup {
+                    order_id: order_id.clone(),
+                },
+            ),
+            (rider, ExecuteMsg::PickUpOrder { order_id }),
+        ] {
+            app.execute_contract(Addr::unchecked(sender), contract_addr.clone(), &msg, &[])
+                .unwrap();
+        }
+    }
+
+    fn deliver_order(
+        app: &mut App,
+        contract_addr: &Addr,
+        restaurant_user: &str,
+        rider: &str,
+        order_id: &str,
+    ) {
+        for (sender, msg) in [
+            (
+                restaurant_user,
+                ExecuteMsg::AcceptOrder {
+                    order_id: order_id.to_string(),
+                },
+            ),
+            (
+                rider,
+                ExecuteMsg::ClaimDelivery {
+                    order_id: order_id.to_string(),
+                },
+            ),
+        ] {
+            app.execute_contract(Addr::unchecked(sender), contract_addr.clone(), &msg, &[])
+                .unwrap();
+        }
+        pick_up_order(app, contract_addr, restaurant_user, rider, order_id);
+        app.execute_contract(
+            Addr::unchecked(rider),
+            contract_addr.clone(),
+            &ExecuteMsg::ConfirmDelivery {
+                order_id: order_id.to_string(),
+                delivery_code: Some(DELIVERY_CODE.to_string()),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    fn withdraw(app: &mut App, contract_addr: &Addr, address: &str) {
+        app.execute_contract(
+            Addr::unchecked(address),
+            contract_addr.clone(),
+            &ExecuteMsg::Withdraw {},
+            &[],
+        )
+        .unwrap();
+    }
+
     mod restaurant_tests {
 
         use tastella::msg::{
-            GetMenuItemsResponse, GetOrderCostResponse, GetOrderStatusResponse, GetOrdersResponse,
-            GetOwnersResponse, GetRiderResponse, OrderItem, UserResponse,
+            GetClaimableBalanceResponse, GetLatestOrderIdResponse, GetMenuItemsResponse,
+            GetOrderCostResponse, GetOrderResponse, GetOrderStatusResponse, GetOrdersResponse,
+            GetOwnersResponse, GetRestaurantOperatorsResponse, GetRestaurantReviewsResponse,
+            GetRiderResponse, GetRiderStatsResponse, GetUserRestaurantsResponse, OrderItem,
+            PlatformConfigResponse, UserResponse,
         };
 
         use super::*;
@@ -183,6 +289,46 @@ mod tests {
             assert!(res.owners.contains(&"xion1newowner".to_string()));
         }
 
+        #[test]
+        fn test_update_platform_config() {
+            let (mut app, contract_addr) = proper_instantiate();
+
+            let update_msg = ExecuteMsg::UpdatePlatformConfig {
+                platform_name: None,
+                platform_description: None,
+                fee_percentage: Some(Decimal::percent(10)),
+                fee_address: None,
+                order_acceptance_timeout: Some(900),
+                delivery_fees: None,
+                rider_suspension_threshold: None,
+                dispute_window: None,
+                accepted_denoms: None,
+                accepted_cw20s: None,
+            };
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &update_msg,
+                &[],
+            )
+            .unwrap_err();
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &update_msg,
+                &[],
+            )
+            .unwrap();
+
+            let res: PlatformConfigResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetPlatformConfig {})
+                .unwrap();
+            assert_eq!(res.fee_percentage, Decimal::percent(10));
+            assert_eq!(res.order_acceptance_timeout, 900);
+            assert_eq!(res.platform_name, "Food Delivery Platform");
+        }
+
         #[test]
         fn test_register_and_get_restaurants() {
             let (mut app, contract_addr) = proper_instantiate();
@@ -196,16 +342,19 @@ mod tests {
                 RESTAURANT_1.clone(),
             );
 
-            let query_msg = QueryMsg::GetRestaurants {};
+            let query_msg = QueryMsg::GetRestaurants {
+                start_after: None,
+                limit: None,
+            };
             let res: GetRestaurantsResponse = app
                 .wrap()
                 .query_wasm_smart(contract_addr.clone(), &query_msg)
                 .unwrap();
 
             assert_eq!(res.restaurants.len(), 1);
-            assert_eq!(res.restaurants[0].name, "Test Restaurant");
+            assert_eq!(res.restaurants[0].restaurant.name, "Test Restaurant");
             assert_eq!(
-                res.restaurants[0].image_uri,
+                res.restaurants[0].restaurant.image_uri,
                 "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco"
             );
         }
@@ -225,7 +374,7 @@ mod tests {
                 RESTAURANT_1.clone(),
             );
 
-            let restaurant_id = format!("restaurant_{}", USER);
+            let restaurant_id = "restaurant_1".to_string();
             println!("restaurant_id: {}", restaurant_id);
 
             add_menu_item(
@@ -244,7 +393,9 @@ mod tests {
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
             };
             let cost_res = app
                 .wrap()
@@ -261,6 +412,7 @@ mod tests {
                 vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
                 vec![Coin {
                     denom: NATIVE_DENOM.to_string(),
@@ -281,6 +433,8 @@ mod tests {
             // get order details
             let order_query = QueryMsg::GetUserOrders {
                 address: Addr::unchecked(USER).to_string(),
+                start_after: None,
+                limit: None,
             };
             let orders_res: GetOrdersResponse = app
                 .wrap()
@@ -317,7 +471,7 @@ mod tests {
                 RESTAURANT_1.clone(),
             );
 
-            let restaurant_id = format!("restaurant_{}", USER);
+            let restaurant_id = "restaurant_1".to_string();
 
             add_menu_item(
                 &mut app,
@@ -335,7 +489,10 @@ mod tests {
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             };
             let res = app
                 .execute_contract(
@@ -369,7 +526,7 @@ mod tests {
 
             assert_eq!(res.escrow.order_id, order_id);
             assert_eq!(res.escrow.amount, Uint128::new(200));
-            assert_eq!(res.escrow.released, false);
+            assert!(!res.escrow.released);
         }
 
         #[test]
@@ -385,7 +542,7 @@ mod tests {
                 RESTAURANT_1.clone(),
             );
 
-            let restaurant_id = format!("restaurant_{}", USER);
+            let restaurant_id = "restaurant_1".to_string();
 
             add_menu_item(
                 &mut app,
@@ -400,6 +557,8 @@ mod tests {
 
             let query_msg = QueryMsg::GetMenuItems {
                 restaurant_id: restaurant_id.clone(),
+                start_after: None,
+                limit: None,
             };
             let res: GetMenuItemsResponse = app
                 .wrap()
@@ -423,7 +582,7 @@ mod tests {
                 RESTAURANT_1.clone(),
             );
 
-            let restaurant_id = format!("restaurant_{}", USER);
+            let restaurant_id = "restaurant_1".to_string();
 
             add_menu_item(
                 &mut app,
@@ -441,7 +600,10 @@ mod tests {
                 items: vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
             };
             app.execute_contract(
                 Addr::unchecked(USER),
@@ -456,6 +618,8 @@ mod tests {
 
             let query_msg = QueryMsg::GetOrdersFromARestaurant {
                 restaurant_id: restaurant_id.clone(),
+                start_after: None,
+                limit: None,
             };
             let res: GetOrdersResponse = app
                 .wrap()
@@ -479,7 +643,7 @@ mod tests {
                 RESTAURANT_1.clone(),
             );
 
-            let restaurant_id = format!("restaurant_{}", USER);
+            let restaurant_id = "restaurant_1".to_string();
 
             add_menu_item(
                 &mut app,
@@ -500,6 +664,7 @@ mod tests {
                 vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
                 vec![Coin {
                     denom: NATIVE_DENOM.to_string(),
@@ -533,7 +698,7 @@ mod tests {
         fn test_register_rider() {
             let (mut app, contract_addr) = proper_instantiate();
 
-            let _res = register_rider(&mut app, &contract_addr, USER, "Test Rider".to_string());
+            register_rider(&mut app, &contract_addr, USER, "Test Rider".to_string());
             let rider_id = format!("rider_{}", USER);
 
             let get_rider = QueryMsg::GetRiderById {
@@ -567,7 +732,7 @@ mod tests {
                 restaurant_address.clone(),
             );
 
-            let restaurant_id = format!("restaurant_{}", USER);
+            let restaurant_id = "restaurant_1".to_string();
             add_menu_item(
                 &mut app,
                 &contract_addr,
@@ -603,6 +768,7 @@ mod tests {
                 vec![OrderItem {
                     item_id: "item_1".to_string(),
                     quantity: 2,
+                    selected_options: vec![],
                 }],
                 vec![Coin {
                     denom: "uxion".to_string(),
@@ -630,7 +796,7 @@ mod tests {
             )
             .unwrap();
 
-            let _ = register_rider(&mut app, &contract_addr, USER, "Test Rider".to_string());
+            register_rider(&mut app, &contract_addr, USER, "Test Rider".to_string());
             let rider_id = format!("rider_{}", USER);
             app.execute_contract(
                 Addr::unchecked(USER),
@@ -642,9 +808,11 @@ mod tests {
                 &[],
             )
             .unwrap();
+            pick_up_order(&mut app, &contract_addr, USER, USER, &order_id);
 
             let confirm_delivery_msg = ExecuteMsg::ConfirmDelivery {
                 order_id: order_id.clone(),
+                delivery_code: Some(DELIVERY_CODE.to_string()),
             };
             app.execute_contract(
                 Addr::unchecked(USER),
@@ -654,6 +822,54 @@ mod tests {
             )
             .unwrap();
 
+            let rider_orders: GetOrdersResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetRiderOrders {
+                        rider_id: rider_id.clone(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(rider_orders.orders.len(), 1);
+            assert_eq!(rider_orders.orders[0].status, OrderStatus::Completed);
+
+            let delivered: GetOrdersResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetAllSuccessfulOrderStatus {
+                        is_delivered: true,
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(delivered.orders.len(), 1);
+            assert_eq!(delivered.orders[0].id, order_id);
+
+            let claimable: GetClaimableBalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetClaimableBalance {
+                        address: restaurant_address.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                claimable.balances,
+                vec![Coin {
+                    denom: "uxion".to_string(),
+                    amount: Uint128::new(190),
+                }]
+            );
+
+            withdraw(&mut app, &contract_addr, FEE_WALLET.as_str());
+            withdraw(&mut app, &contract_addr, restaurant_address.as_str());
+
             let fee_balance = app.wrap().query_balance(&*FEE_WALLET, "uxion").unwrap();
             assert_eq!(fee_balance.amount, Uint128::new(10));
 
@@ -694,7 +910,1580 @@ mod tests {
             assert_eq!(response.wallet, sender);
             assert_eq!(response.id, generated_id);
             assert_eq!(response.phone_number, "1234567890");
-            assert_eq!(response.is_registered, true);
+            assert!(response.is_registered);
+        }
+
+        #[test]
+        fn test_latest_order_id_after_many_orders() {
+            let (mut app, contract_addr) = proper_instantiate();
+
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+
+            let restaurant_id = "restaurant_1".to_string();
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                &restaurant_id,
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+
+            for _ in 0..10 {
+                create_order(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    &restaurant_id,
+                    vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 1,
+                        selected_options: vec![],
+                    }],
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(100),
+                    }],
+                )
+                .unwrap();
+            }
+
+            let res: GetLatestOrderIdResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetLatestOrderId {
+                        address: USER.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.order_id, Some("order_00000000000000000010".to_string()));
+
+            let orders: GetOrdersResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetUserOrders {
+                        address: USER.to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(orders.orders.len(), 10);
+            assert_eq!(orders.orders[1].id, "order_00000000000000000002");
+            assert_eq!(orders.orders[9].id, "order_00000000000000000010");
+        }
+
+        #[test]
+        fn test_riders_claim_open_delivery_jobs() {
+            let (mut app, contract_addr) = proper_instantiate();
+
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+
+            let restaurant_id = "restaurant_1".to_string();
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                &restaurant_id,
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+
+            create_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                &restaurant_id,
+                vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 1,
+                    selected_options: vec![],
+                }],
+                vec![Coin {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(100),
+                }],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::AcceptOrder {
+                    order_id: "order_00000000000000000001".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            let open_jobs_query = QueryMsg::GetOpenDeliveryJobs {
+                start_after: None,
+                limit: None,
+            };
+            let jobs: GetOrdersResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &open_jobs_query)
+                .unwrap();
+            assert_eq!(jobs.orders.len(), 1);
+            assert_eq!(jobs.orders[0].id, "order_00000000000000000001");
+
+            register_rider(
+                &mut app,
+                &contract_addr,
+                "xion1rider1",
+                "Rider One".to_string(),
+            );
+            register_rider(
+                &mut app,
+                &contract_addr,
+                "xion1rider2",
+                "Rider Two".to_string(),
+            );
+
+            let claim_msg = ExecuteMsg::ClaimDelivery {
+                order_id: "order_00000000000000000001".to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked("xion1rider1"),
+                contract_addr.clone(),
+                &claim_msg,
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked("xion1rider2"),
+                    contract_addr.clone(),
+                    &claim_msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::OrderNotAccepted {}
+            );
+
+            let jobs: GetOrdersResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &open_jobs_query)
+                .unwrap();
+            assert!(jobs.orders.is_empty());
+
+            let status: GetOrderStatusResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetOrderStatusById {
+                        order_id: "order_00000000000000000001".to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(status.status, OrderStatus::Accepted);
+        }
+
+        #[test]
+        fn test_open_delivery_jobs_paging() {
+            let (mut app, contract_addr) = proper_instantiate();
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            let restaurant_id = "restaurant_1".to_string();
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                &restaurant_id,
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+            register_rider(
+                &mut app,
+                &contract_addr,
+                "xion1rider1",
+                "Rider One".to_string(),
+            );
+
+            let order_ids: Vec<String> = (1..=5).map(|n| format!("order_{:020}", n)).collect();
+            for order_id in &order_ids {
+                create_order(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    &restaurant_id,
+                    vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 1,
+                        selected_options: vec![],
+                    }],
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(100),
+                    }],
+                )
+                .unwrap();
+                app.execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &ExecuteMsg::AcceptOrder {
+                        order_id: order_id.clone(),
+                    },
+                    &[],
+                )
+                .unwrap();
+            }
+
+            // Order 1 stays accepted, 2 is preparing and 3 is ready for pickup.
+            // Order 4 is claimed, while order 5 is claimed and then declined.
+            let steps = [
+                (
+                    USER,
+                    ExecuteMsg::StartPreparing {
+                        order_id: order_ids[1].clone(),
+                    },
+                ),
+                (
+                    USER,
+                    ExecuteMsg::StartPreparing {
+                        order_id: order_ids[2].clone(),
+                    },
+                ),
+                (
+                    USER,
+                    ExecuteMsg::MarkReadyForPickup {
+                        order_id: order_ids[2].clone(),
+                    },
+                ),
+                (
+                    "xion1rider1",
+                    ExecuteMsg::ClaimDelivery {
+                        order_id: order_ids[3].clone(),
+                    },
+                ),
+                (
+                    "xion1rider1",
+                    ExecuteMsg::ClaimDelivery {
+                        order_id: order_ids[4].clone(),
+                    },
+                ),
+                (
+                    "xion1rider1",
+                    ExecuteMsg::DeclineDelivery {
+                        order_id: order_ids[4].clone(),
+                    },
+                ),
+            ];
+            for (sender, msg) in steps {
+                app.execute_contract(Addr::unchecked(sender), contract_addr.clone(), &msg, &[])
+                    .unwrap();
+            }
+
+            let mut pages: Vec<Vec<String>> = vec![];
+            let mut start_after = None;
+            loop {
+                let jobs: GetOrdersResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        contract_addr.clone(),
+                        &QueryMsg::GetOpenDeliveryJobs {
+                            start_after: start_after.clone(),
+                            limit: Some(2),
+                        },
+                    )
+                    .unwrap();
+                pages.push(jobs.orders.into_iter().map(|order| order.id).collect());
+                start_after = jobs.next_start_after;
+                if start_after.is_none() {
+                    break;
+                }
+            }
+            assert_eq!(
+                pages,
+                vec![
+                    vec![order_ids[0].clone(), order_ids[1].clone()],
+                    vec![order_ids[2].clone(), order_ids[4].clone()],
+                    vec![],
+                ]
+            );
+        }
+
+        #[test]
+        fn test_rider_paid_delivery_fee_and_tip() {
+            let (mut app, contract_addr) = proper_instantiate();
+
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &ExecuteMsg::UpdatePlatformConfig {
+                    platform_name: None,
+                    platform_description: None,
+                    fee_percentage: None,
+                    fee_address: None,
+                    order_acceptance_timeout: None,
+                    delivery_fees: Some(vec![Coin::new(20, NATIVE_DENOM)]),
+                    rider_suspension_threshold: None,
+                    dispute_window: None,
+                    accepted_denoms: None,
+                    accepted_cw20s: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+
+            let restaurant_id = "restaurant_1".to_string();
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                &restaurant_id,
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+
+            let items = vec![OrderItem {
+                item_id: "item_1".to_string(),
+                quantity: 2,
+                selected_options: vec![],
+            }];
+            let cost: GetOrderCostResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetOrderCost {
+                        restaurant_id: restaurant_id.clone(),
+                        items: items.clone(),
+                        tip: Some(Uint128::new(5)),
+                    },
+                )
+                .unwrap();
+            assert_eq!(cost.subtotal, Uint128::new(200));
+            assert_eq!(cost.delivery_fee, Uint128::new(20));
+            assert_eq!(cost.tip, Uint128::new(5));
+            assert_eq!(cost.total, Uint128::new(225));
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::CreateOrder {
+                    restaurant_id: restaurant_id.clone(),
+                    items,
+                    tip: Some(Uint128::new(5)),
+                    delivery_code_hash: delivery_code_hash(),
+                },
+                &[Coin {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: cost.total,
+                }],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::AcceptOrder {
+                    order_id: "order_00000000000000000001".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            let rider = "xion1rider1";
+            register_rider(&mut app, &contract_addr, rider, "Rider One".to_string());
+            app.execute_contract(
+                Addr::unchecked(rider),
+                contract_addr.clone(),
+                &ExecuteMsg::ClaimDelivery {
+                    order_id: "order_00000000000000000001".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+            pick_up_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                rider,
+                "order_00000000000000000001",
+            );
+            app.execute_contract(
+                Addr::unchecked(rider),
+                contract_addr.clone(),
+                &ExecuteMsg::ConfirmDelivery {
+                    order_id: "order_00000000000000000001".to_string(),
+                    delivery_code: Some(DELIVERY_CODE.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+
+            withdraw(&mut app, &contract_addr, FEE_WALLET.as_str());
+            withdraw(&mut app, &contract_addr, RESTAURANT_1.as_str());
+            withdraw(&mut app, &contract_addr, rider);
+
+            let fee_balance = app
+                .wrap()
+                .query_balance(&*FEE_WALLET, NATIVE_DENOM)
+                .unwrap();
+            assert_eq!(fee_balance.amount, Uint128::new(10));
+            let restaurant_balance = app
+                .wrap()
+                .query_balance(&*RESTAURANT_1, NATIVE_DENOM)
+                .unwrap();
+            assert_eq!(restaurant_balance.amount, Uint128::new(190));
+            let rider_balance = app.wrap().query_balance(rider, NATIVE_DENOM).unwrap();
+            assert_eq!(rider_balance.amount, Uint128::new(25));
+        }
+
+        #[test]
+        fn test_owner_runs_multiple_restaurants() {
+            let (mut app, contract_addr) = proper_instantiate();
+
+            for name in ["Downtown", "Uptown"] {
+                register_restaurant(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    name,
+                    "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                    RESTAURANT_1.clone(),
+                );
+            }
+
+            let res: GetUserRestaurantsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetUserOwnedRestaurants {
+                        owner: USER.to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.restaurants.len(), 2);
+            assert_eq!(res.restaurants[0].id, "restaurant_1");
+            assert_eq!(res.restaurants[0].name, "Downtown");
+            assert_eq!(res.restaurants[1].id, "restaurant_2");
+            assert_eq!(res.restaurants[1].name, "Uptown");
+
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                "restaurant_2",
+                "item_1",
+                "Burger",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER_2),
+                    contract_addr.clone(),
+                    &ExecuteMsg::AddMenuItem {
+                        restaurant_id: "restaurant_2".to_string(),
+                        item_id: "item_2".to_string(),
+                        name: "Fries".to_string(),
+                        price: Uint128::new(50),
+                        image_uri: "https://test.com/fries".to_string(),
+                        dietary_info: DietaryInfo::default(),
+                        denom: None,
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            );
+
+            let menu: GetMenuItemsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetMenuItems {
+                        restaurant_id: "restaurant_2".to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(menu.menu_items.len(), 1);
+            assert_eq!(menu.menu_items[0].name, "Burger");
+        }
+
+        #[test]
+        fn test_restaurant_operator_roles() {
+            let (mut app, contract_addr) = proper_instantiate();
+            let kitchen = "xion1kitchen";
+            let manager = "xion1manager";
+
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            let restaurant_id = "restaurant_1".to_string();
+
+            for (operator, role) in [
+                (kitchen, RestaurantRole::Kitchen),
+                (manager, RestaurantRole::Manager),
+            ] {
+                app.execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &ExecuteMsg::GrantRestaurantRole {
+                        restaurant_id: restaurant_id.clone(),
+                        operator: operator.to_string(),
+                        role,
+                    },
+                    &[],
+                )
+                .unwrap();
+            }
+
+            let res: GetRestaurantOperatorsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetRestaurantOperators {
+                        restaurant_id: restaurant_id.clone(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.operators.len(), 2);
+            assert_eq!(res.operators[0].address, kitchen);
+            assert_eq!(res.operators[0].role, RestaurantRole::Kitchen);
+
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                manager,
+                &restaurant_id,
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(kitchen),
+                    contract_addr.clone(),
+                    &ExecuteMsg::ToggleMenuItemAvailability {
+                        restaurant_id: restaurant_id.clone(),
+                        item_id: "item_1".to_string(),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            );
+
+            create_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                &restaurant_id,
+                vec![OrderItem {
+                    item_id: "item_1".to_string(),
+                    quantity: 1,
+                    selected_options: vec![],
+                }],
+                vec![Coin {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(100),
+                }],
+            )
+            .unwrap();
+
+            let accept_msg = ExecuteMsg::AcceptOrder {
+                order_id: "order_00000000000000000001".to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(manager),
+                    contract_addr.clone(),
+                    &accept_msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            );
+            app.execute_contract(
+                Addr::unchecked(kitchen),
+                contract_addr.clone(),
+                &accept_msg,
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::RevokeRestaurantRole {
+                    restaurant_id: restaurant_id.clone(),
+                    operator: manager.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(manager),
+                    contract_addr.clone(),
+                    &ExecuteMsg::RemoveMenuItem {
+                        restaurant_id: restaurant_id.clone(),
+                        item_id: "item_1".to_string(),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            );
+        }
+
+        #[test]
+        fn test_restaurant_reviews() {
+            let (mut app, contract_addr) = proper_instantiate();
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                "restaurant_1",
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+            for _ in 0..2 {
+                create_order(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    "restaurant_1",
+                    vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 1,
+                        selected_options: vec![],
+                    }],
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(100),
+                    }],
+                )
+                .unwrap();
+            }
+            let rider = "xion1rider1";
+            register_rider(&mut app, &contract_addr, rider, "Rider One".to_string());
+            deliver_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                rider,
+                "order_00000000000000000001",
+            );
+
+            let review = |order_id: &str, rating: u8| ExecuteMsg::ReviewRestaurant {
+                order_id: order_id.to_string(),
+                rating,
+                comment: "Great pizza".to_string(),
+            };
+            for (sender, msg, expected) in [
+                (
+                    rider,
+                    review("order_00000000000000000001", 4),
+                    ContractError::Unauthorized {},
+                ),
+                (
+                    USER,
+                    review("order_00000000000000000002", 4),
+                    ContractError::OrderNotCompleted {},
+                ),
+                (
+                    USER,
+                    review("order_00000000000000000001", 6),
+                    ContractError::InvalidRating {},
+                ),
+            ] {
+                let err = app
+                    .execute_contract(Addr::unchecked(sender), contract_addr.clone(), &msg, &[])
+                    .unwrap_err();
+                assert_eq!(err.downcast::<ContractError>().unwrap(), expected);
+            }
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &review("order_00000000000000000001", 4),
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &review("order_00000000000000000001", 5),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::OrderAlreadyReviewed {}
+            );
+
+            deliver_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                rider,
+                "order_00000000000000000002",
+            );
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &review("order_00000000000000000002", 5),
+                &[],
+            )
+            .unwrap();
+
+            let res: GetRestaurantReviewsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetRestaurantReviews {
+                        restaurant_id: "restaurant_1".to_string(),
+                        start_after: None,
+                        limit: Some(1),
+                    },
+                )
+                .unwrap();
+            assert_eq!(res.reviews.len(), 1);
+            assert_eq!(res.reviews[0].rating, 4);
+            assert_eq!(res.rating_count, 2);
+            assert_eq!(res.average_rating, Some(Decimal::from_ratio(9u128, 2u128)));
+            assert_eq!(
+                res.next_start_after,
+                Some("order_00000000000000000001".to_string())
+            );
+
+            let res: GetRestaurantsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr,
+                    &QueryMsg::GetRestaurants {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                res.restaurants[0].average_rating,
+                Some(Decimal::from_ratio(9u128, 2u128))
+            );
+        }
+
+        #[test]
+        fn test_rider_ratings_and_suspension() {
+            let (mut app, contract_addr) = proper_instantiate();
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &ExecuteMsg::UpdatePlatformConfig {
+                    platform_name: None,
+                    platform_description: None,
+                    fee_percentage: None,
+                    fee_address: None,
+                    order_acceptance_timeout: None,
+                    delivery_fees: None,
+                    rider_suspension_threshold: Some(Decimal::percent(300)),
+                    dispute_window: None,
+                    accepted_denoms: None,
+                    accepted_cw20s: None,
+                },
+                &[],
+            )
+            .unwrap();
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                "restaurant_1",
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+            for _ in 0..6 {
+                create_order(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    "restaurant_1",
+                    vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 1,
+                        selected_options: vec![],
+                    }],
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(100),
+                    }],
+                )
+                .unwrap();
+            }
+            let rider = "xion1rider1";
+            let rider_id = format!("rider_{}", rider);
+            register_rider(&mut app, &contract_addr, rider, "Rider One".to_string());
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::AcceptOrder {
+                    order_id: "order_00000000000000000006".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+            for msg in [
+                ExecuteMsg::ClaimDelivery {
+                    order_id: "order_00000000000000000006".to_string(),
+                },
+                ExecuteMsg::DeclineDelivery {
+                    order_id: "order_00000000000000000006".to_string(),
+                },
+            ] {
+                app.execute_contract(Addr::unchecked(rider), contract_addr.clone(), &msg, &[])
+                    .unwrap();
+            }
+
+            for n in 1..=5 {
+                let order_id = format!("order_{:020}", n);
+                deliver_order(&mut app, &contract_addr, USER, rider, &order_id);
+                app.execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &ExecuteMsg::RateRider {
+                        order_id: order_id.clone(),
+                        rating: 1,
+                    },
+                    &[],
+                )
+                .unwrap();
+                if n == 1 {
+                    let err = app
+                        .execute_contract(
+                            Addr::unchecked(USER),
+                            contract_addr.clone(),
+                            &ExecuteMsg::RateRider {
+                                order_id,
+                                rating: 5,
+                            },
+                            &[],
+                        )
+                        .unwrap_err();
+                    assert_eq!(
+                        err.downcast::<ContractError>().unwrap(),
+                        ContractError::RiderAlreadyRated {}
+                    );
+                }
+            }
+
+            let stats: GetRiderStatsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetRiderStats {
+                        rider_id: rider_id.clone(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(stats.deliveries_completed, 5);
+            assert_eq!(stats.cancellations, 1);
+            assert_eq!(stats.rating_count, 5);
+            assert_eq!(stats.average_rating, Some(Decimal::one()));
+            assert!(!stats.is_registered);
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(rider),
+                    contract_addr.clone(),
+                    &ExecuteMsg::ClaimDelivery {
+                        order_id: "order_00000000000000000006".to_string(),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::RiderNotRegistered {}
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr,
+                    &ExecuteMsg::AssignRider {
+                        order_id: "order_00000000000000000006".to_string(),
+                        rider_id,
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::RiderNotRegistered {}
+            );
+        }
+
+        #[test]
+        fn test_dispute_resolution() {
+            let (mut app, contract_addr) = proper_instantiate();
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &ExecuteMsg::UpdatePlatformConfig {
+                    platform_name: None,
+                    platform_description: None,
+                    fee_percentage: None,
+                    fee_address: None,
+                    order_acceptance_timeout: None,
+                    delivery_fees: None,
+                    rider_suspension_threshold: None,
+                    dispute_window: Some(3600),
+                    accepted_denoms: None,
+                    accepted_cw20s: None,
+                },
+                &[],
+            )
+            .unwrap();
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            add_menu_item(
+                &mut app,
+                &contract_addr,
+                USER,
+                "restaurant_1",
+                "item_1",
+                "Pizza",
+                Uint128::new(100),
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+            );
+            for _ in 0..2 {
+                create_order(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    "restaurant_1",
+                    vec![OrderItem {
+                        item_id: "item_1".to_string(),
+                        quantity: 2,
+                        selected_options: vec![],
+                    }],
+                    vec![Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(200),
+                    }],
+                )
+                .unwrap();
+            }
+            let rider = "xion1rider1";
+            register_rider(&mut app, &contract_addr, rider, "Rider One".to_string());
+            deliver_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                rider,
+                "order_00000000000000000001",
+            );
+            deliver_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                rider,
+                "order_00000000000000000002",
+            );
+
+            let settle = |order_id: &str| ExecuteMsg::SettleOrder {
+                order_id: order_id.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(rider),
+                    contract_addr.clone(),
+                    &settle("order_00000000000000000001"),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::DisputeWindowOpen {}
+            );
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::OpenDispute {
+                    order_id: "order_00000000000000000001".to_string(),
+                    reason: "Food never arrived".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+            let resolve = |restaurant_amount: u128| ExecuteMsg::ResolveDispute {
+                order_id: "order_00000000000000000001".to_string(),
+                customer_amount: Uint128::new(150),
+                restaurant_amount: Uint128::new(restaurant_amount),
+                rider_amount: Uint128::zero(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &resolve(50),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::Unauthorized {}
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER_2),
+                    contract_addr.clone(),
+                    &resolve(60),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::InvalidDisputeSplit {}
+            );
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &resolve(50),
+                &[],
+            )
+            .unwrap();
+
+            let order: GetOrderResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetOrderDetails {
+                        id: "order_00000000000000000001".to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(order.order.status, OrderStatus::Resolved);
+            assert!(order.order.completed_at.is_some());
+            let resolution = order.order.dispute.unwrap().resolution.unwrap();
+            assert_eq!(resolution.resolved_by, Addr::unchecked(USER_2));
+            assert_eq!(resolution.customer_amount, Uint128::new(150));
+            assert_eq!(
+                app.wrap().query_balance(USER, NATIVE_DENOM).unwrap().amount,
+                Uint128::new(99750)
+            );
+            assert_eq!(
+                app.wrap()
+                    .query_balance(&*RESTAURANT_1, NATIVE_DENOM)
+                    .unwrap()
+                    .amount,
+                Uint128::new(50)
+            );
+
+            app.update_block(|block| block.time = block.time.plus_seconds(3601));
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &ExecuteMsg::OpenDispute {
+                        order_id: "order_00000000000000000002".to_string(),
+                        reason: "Cold pizza".to_string(),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::DisputeWindowClosed {}
+            );
+            app.execute_contract(
+                Addr::unchecked(rider),
+                contract_addr.clone(),
+                &settle("order_00000000000000000002"),
+                &[],
+            )
+            .unwrap();
+            withdraw(&mut app, &contract_addr, RESTAURANT_1.as_str());
+            assert_eq!(
+                app.wrap()
+                    .query_balance(&*RESTAURANT_1, NATIVE_DENOM)
+                    .unwrap()
+                    .amount,
+                Uint128::new(240)
+            );
+        }
+
+        #[test]
+        fn test_orders_paid_in_restaurant_denom() {
+            let (mut app, contract_addr) = proper_instantiate();
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            for item_id in ["item_1", "item_2"] {
+                add_menu_item(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    "restaurant_1",
+                    item_id,
+                    "Pizza",
+                    Uint128::new(100),
+                    "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                );
+            }
+
+            let set_usdc = ExecuteMsg::UpdateRestaurant {
+                restaurant_id: "restaurant_1".to_string(),
+                name: None,
+                image_uri: None,
+                physical_address: None,
+                payout_address: None,
+                denom: Some(USDC_DENOM.to_string()),
+            };
+            let err = app
+                .execute_contract(Addr::unchecked(USER), contract_addr.clone(), &set_usdc, &[])
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::DenomNotAccepted {
+                    denom: USDC_DENOM.to_string()
+                }
+            );
+            let register_usdc = ExecuteMsg::RegisterRestaurant {
+                name: "USDC Restaurant".to_string(),
+                image_uri: "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco"
+                    .to_string(),
+                physical_address: "2 Test Street".to_string(),
+                payout_address: RESTAURANT_2.to_string(),
+                denom: Some(USDC_DENOM.to_string()),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &register_usdc,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::DenomNotAccepted {
+                    denom: USDC_DENOM.to_string()
+                }
+            );
+            let whitelist = |denoms: Vec<&str>| ExecuteMsg::UpdatePlatformConfig {
+                platform_name: None,
+                platform_description: None,
+                fee_percentage: None,
+                fee_address: None,
+                order_acceptance_timeout: None,
+                delivery_fees: Some(denoms.iter().map(|denom| Coin::new(0, *denom)).collect()),
+                rider_suspension_threshold: None,
+                dispute_window: None,
+                accepted_denoms: Some(denoms.into_iter().map(String::from).collect()),
+                accepted_cw20s: None,
+            };
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &whitelist(vec![NATIVE_DENOM, USDC_DENOM]),
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(Addr::unchecked(USER), contract_addr.clone(), &set_usdc, &[])
+                .unwrap();
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &register_usdc,
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::UpdateMenuItem {
+                    restaurant_id: "restaurant_1".to_string(),
+                    item_id: "item_2".to_string(),
+                    name: None,
+                    price: None,
+                    available: None,
+                    image_uri: None,
+                    dietary_info: None,
+                    denom: Some(NATIVE_DENOM.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+
+            let order_msg = |item_ids: &[&str]| ExecuteMsg::CreateOrder {
+                restaurant_id: "restaurant_1".to_string(),
+                items: item_ids
+                    .iter()
+                    .map(|item_id| OrderItem {
+                        item_id: item_id.to_string(),
+                        quantity: 2,
+                        selected_options: vec![],
+                    })
+                    .collect(),
+                tip: None,
+                delivery_code_hash: delivery_code_hash(),
+            };
+            let cost: GetOrderCostResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetOrderCost {
+                        restaurant_id: "restaurant_1".to_string(),
+                        items: vec![OrderItem {
+                            item_id: "item_1".to_string(),
+                            quantity: 2,
+                            selected_options: vec![],
+                        }],
+                        tip: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(cost.denom, USDC_DENOM);
+
+            let usdc = |amount: u128| Coin {
+                denom: USDC_DENOM.to_string(),
+                amount: Uint128::new(amount),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &order_msg(&["item_1", "item_2"]),
+                    &[usdc(400)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::MixedDenoms {}
+            );
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr.clone(),
+                    &order_msg(&["item_1"]),
+                    &[Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(200),
+                    }],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::IncorrectPayment {}
+            );
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &order_msg(&["item_1"]),
+                &[usdc(200)],
+            )
+            .unwrap();
+
+            let escrow: GetEscrowResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetEscrow {
+                        order_id: "order_00000000000000000001".to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(escrow.escrow.denom, USDC_DENOM);
+
+            let rider = "xion1rider1";
+            register_rider(&mut app, &contract_addr, rider, "Rider One".to_string());
+            deliver_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                rider,
+                "order_00000000000000000001",
+            );
+            withdraw(&mut app, &contract_addr, FEE_WALLET.as_str());
+            withdraw(&mut app, &contract_addr, RESTAURANT_1.as_str());
+            assert_eq!(
+                app.wrap().query_balance(&*FEE_WALLET, USDC_DENOM).unwrap(),
+                usdc(10)
+            );
+            assert_eq!(
+                app.wrap()
+                    .query_balance(&*RESTAURANT_1, USDC_DENOM)
+                    .unwrap(),
+                usdc(190)
+            );
+
+            // Dropping a denom from the whitelist stops new orders in it.
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &whitelist(vec![NATIVE_DENOM]),
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr,
+                    &order_msg(&["item_1"]),
+                    &[usdc(200)],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::DenomNotAccepted {
+                    denom: USDC_DENOM.to_string()
+                }
+            );
+        }
+
+        #[test]
+        fn test_delivery_fee_per_denom() {
+            let (mut app, contract_addr) = proper_instantiate();
+            register_restaurant(
+                &mut app,
+                &contract_addr,
+                USER,
+                "Test Restaurant",
+                "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                RESTAURANT_1.clone(),
+            );
+            for item_id in ["item_1", "item_2"] {
+                add_menu_item(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    "restaurant_1",
+                    item_id,
+                    "Pizza",
+                    Uint128::new(100),
+                    "https://ipfs.io/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco",
+                );
+            }
+            let update_config =
+                |delivery_fees: Option<Vec<Coin>>| ExecuteMsg::UpdatePlatformConfig {
+                    platform_name: None,
+                    platform_description: None,
+                    fee_percentage: None,
+                    fee_address: None,
+                    order_acceptance_timeout: None,
+                    delivery_fees,
+                    rider_suspension_threshold: None,
+                    dispute_window: None,
+                    accepted_denoms: Some(vec![NATIVE_DENOM.to_string(), USDC_DENOM.to_string()]),
+                    accepted_cw20s: None,
+                };
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &update_config(None),
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &ExecuteMsg::UpdateMenuItem {
+                    restaurant_id: "restaurant_1".to_string(),
+                    item_id: "item_2".to_string(),
+                    name: None,
+                    price: None,
+                    available: None,
+                    image_uri: None,
+                    dietary_info: None,
+                    denom: Some(USDC_DENOM.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+
+            let items = |item_id: &str| {
+                vec![OrderItem {
+                    item_id: item_id.to_string(),
+                    quantity: 1,
+                    selected_options: vec![],
+                }]
+            };
+            let coin = |amount: u128, denom: &str| Coin {
+                denom: denom.to_string(),
+                amount: Uint128::new(amount),
+            };
+
+            // The instantiate default only covers the native denom.
+            let err = create_order(
+                &mut app,
+                &contract_addr,
+                USER,
+                "restaurant_1",
+                items("item_2"),
+                vec![coin(100, USDC_DENOM)],
+            )
+            .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::DeliveryFeeNotConfigured {
+                    denom: USDC_DENOM.to_string()
+                }
+            );
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER_2),
+                    contract_addr.clone(),
+                    &update_config(Some(vec![coin(20, NATIVE_DENOM), coin(3, NATIVE_DENOM)])),
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                err.downcast::<ContractError>().unwrap(),
+                ContractError::InvalidDeliveryFees {}
+            );
+            app.execute_contract(
+                Addr::unchecked(USER_2),
+                contract_addr.clone(),
+                &update_config(Some(vec![coin(20, NATIVE_DENOM), coin(3, USDC_DENOM)])),
+                &[],
+            )
+            .unwrap();
+
+            for (item_id, denom, delivery_fee) in
+                [("item_1", NATIVE_DENOM, 20), ("item_2", USDC_DENOM, 3)]
+            {
+                let cost: GetOrderCostResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        contract_addr.clone(),
+                        &QueryMsg::GetOrderCost {
+                            restaurant_id: "restaurant_1".to_string(),
+                            items: items(item_id),
+                            tip: None,
+                        },
+                    )
+                    .unwrap();
+                assert_eq!(cost.denom, denom);
+                assert_eq!(cost.delivery_fee, Uint128::new(delivery_fee));
+                assert_eq!(cost.total, Uint128::new(100 + delivery_fee));
+
+                create_order(
+                    &mut app,
+                    &contract_addr,
+                    USER,
+                    "restaurant_1",
+                    items(item_id),
+                    vec![coin(100 + delivery_fee, denom)],
+                )
+                .unwrap();
+            }
+
+            for (order_id, denom, amount) in [
+                ("order_00000000000000000001", NATIVE_DENOM, 120),
+                ("order_00000000000000000002", USDC_DENOM, 103),
+            ] {
+                let escrow: GetEscrowResponse = app
+                    .wrap()
+                    .query_wasm_smart(
+                        contract_addr.clone(),
+                        &QueryMsg::GetEscrow {
+                            order_id: order_id.to_string(),
+                        },
+                    )
+                    .unwrap();
+                assert_eq!(escrow.escrow.denom, denom);
+                assert_eq!(escrow.escrow.amount, Uint128::new(amount));
+            }
         }
     }
 }